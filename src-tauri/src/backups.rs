@@ -0,0 +1,288 @@
+// Config backups. Every write to a managed config file first snapshots the old
+// content into config-backups/, together with when and why it was replaced,
+// so a bad edit can be diffed against and rolled back.
+
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::ErrorKind;
+use std::path::{Component, Path, PathBuf};
+
+use crate::get_project_root;
+
+// Files that get a history, matched by file name
+const MANAGED_FILES: &[&str] = &["httpd.conf", "my.cnf", "php.ini", "phpmyadmin.conf", "config.inc.php", "php-projects.conf"];
+
+// Older snapshots beyond this many per file are pruned
+const MAX_BACKUPS_PER_FILE: usize = 50;
+
+// Pseudo version id for the live file in diffs
+const CURRENT: &str = "current";
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ConfigBackup {
+    pub id: String,
+    // Path of the original file relative to the DevStackBox root ("config/httpd.conf")
+    pub file: String,
+    pub reason: String,
+    pub created_at: String,
+    pub size: u64,
+}
+
+#[derive(Serialize)]
+pub struct DiffLine {
+    // "context", "added" or "removed"
+    kind: &'static str,
+    text: String,
+}
+
+pub fn backups_root(base_path: &Path) -> PathBuf {
+    base_path.join("config-backups")
+}
+
+// "config/httpd.conf" for <root>/config/httpd.conf
+fn relative_name(base_path: &Path, path: &Path) -> Result<String, String> {
+    let relative = path
+        .strip_prefix(base_path)
+        .map_err(|_| format!("{} is outside the DevStackBox directory", path.display()))?;
+    Ok(relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/"))
+}
+
+// Resolve a relative name from the frontend, refusing anything that is not a
+// managed config file inside the DevStackBox directory
+fn managed_path(base_path: &Path, file: &str) -> Result<PathBuf, String> {
+    let relative = Path::new(file);
+    let safe = relative.components().all(|component| matches!(component, Component::Normal(_)));
+    let managed = relative
+        .file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| MANAGED_FILES.contains(&name));
+
+    if safe && managed {
+        Ok(base_path.join(relative))
+    } else {
+        Err(format!("{} is not a managed config file", file))
+    }
+}
+
+// Snapshots of one file live together: config-backups/config-httpd.conf/
+fn history_dir(base_path: &Path, file: &str) -> PathBuf {
+    backups_root(base_path).join(file.replace('/', "-"))
+}
+
+fn metadata_path(snapshot: &Path) -> PathBuf {
+    snapshot.with_extension("json")
+}
+
+// Copy the current content of `path` into its history
+pub fn snapshot(base_path: &Path, path: &Path, reason: &str) -> Result<Option<ConfigBackup>, String> {
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let file = relative_name(base_path, path)?;
+    let dir = history_dir(base_path, &file);
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+
+    // Snapshots within the same millisecond (a port change and a settings
+    // change rewriting the same file) get "-01", "-02", ... appended;
+    // create_new claims the name so none overwrites another
+    let now = chrono::Local::now();
+    let stamp = now.format("%Y%m%d-%H%M%S%.3f").to_string();
+    let mut attempt = 0;
+    let (id, snapshot_path, mut snapshot_file) = loop {
+        let id = if attempt == 0 { stamp.clone() } else { format!("{}-{:02}", stamp, attempt) };
+        let snapshot_path = dir.join(format!("{}.bak", id));
+        match OpenOptions::new().write(true).create_new(true).open(&snapshot_path) {
+            Ok(file) => break (id, snapshot_path, file),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => attempt += 1,
+            Err(e) => return Err(format!("Failed to back up {}: {}", path.display(), e)),
+        }
+    };
+    let size = File::open(path)
+        .and_then(|mut original| std::io::copy(&mut original, &mut snapshot_file))
+        .map_err(|e| format!("Failed to back up {}: {}", path.display(), e))?;
+
+    let backup = ConfigBackup {
+        id,
+        file,
+        reason: reason.to_string(),
+        created_at: now.to_rfc3339(),
+        size,
+    };
+    let metadata = serde_json::to_string_pretty(&backup).map_err(|e| e.to_string())?;
+    std::fs::write(metadata_path(&snapshot_path), metadata).map_err(|e| e.to_string())?;
+
+    prune(&dir)?;
+    Ok(Some(backup))
+}
+
+// Write a config file, snapshotting the previous version first. Writing the
+// content the file already has is a no-op and leaves no backup behind.
+pub fn write_config_file(base_path: &Path, path: &Path, content: &str, reason: &str) -> Result<(), String> {
+    if let Ok(existing) = std::fs::read_to_string(path) {
+        if existing == content {
+            return Ok(());
+        }
+        snapshot(base_path, path, reason)?;
+    }
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    std::fs::write(path, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn history(dir: &Path) -> Vec<ConfigBackup> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut backups: Vec<ConfigBackup> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "json"))
+        .filter_map(|path| std::fs::read_to_string(path).ok())
+        .filter_map(|metadata| serde_json::from_str(&metadata).ok())
+        .collect();
+
+    // Ids are timestamps, newest first
+    backups.sort_by(|a, b| b.id.cmp(&a.id));
+    backups
+}
+
+fn prune(dir: &Path) -> Result<(), String> {
+    for old in history(dir).into_iter().skip(MAX_BACKUPS_PER_FILE) {
+        let snapshot_path = dir.join(format!("{}.bak", old.id));
+        std::fs::remove_file(metadata_path(&snapshot_path)).map_err(|e| e.to_string())?;
+        let _ = std::fs::remove_file(snapshot_path);
+    }
+    Ok(())
+}
+
+fn read_version(base_path: &Path, file: &str, id: &str) -> Result<String, String> {
+    let path = if id == CURRENT {
+        managed_path(base_path, file)?
+    } else {
+        // Ids only ever contain digits, '-' and '.'
+        if !id.chars().all(|c| c.is_ascii_digit() || c == '-' || c == '.') {
+            return Err(format!("Invalid backup id: {}", id));
+        }
+        history_dir(base_path, file).join(format!("{}.bak", id))
+    };
+    std::fs::read_to_string(&path).map_err(|_| format!("No version {} of {}", id, file))
+}
+
+// Line diff via longest common subsequence; config files are small enough
+// that the quadratic table is not a concern
+fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let line = |kind, text: &str| DiffLine { kind, text: text.to_string() };
+    let mut diff = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            diff.push(line("context", old[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            diff.push(line("removed", old[i]));
+            i += 1;
+        } else {
+            diff.push(line("added", new[j]));
+            j += 1;
+        }
+    }
+    diff.extend(old[i..].iter().map(|text| line("removed", text)));
+    diff.extend(new[j..].iter().map(|text| line("added", text)));
+    diff
+}
+
+#[tauri::command]
+pub async fn list_config_backups(file: Option<String>) -> Result<Vec<ConfigBackup>, String> {
+    let base_path = get_project_root()?;
+
+    if let Some(file) = file {
+        managed_path(&base_path, &file)?;
+        return Ok(history(&history_dir(&base_path, &file)));
+    }
+
+    let mut backups = Vec::new();
+    if let Ok(entries) = std::fs::read_dir(backups_root(&base_path)) {
+        for entry in entries.flatten() {
+            backups.extend(history(&entry.path()));
+        }
+    }
+    backups.sort_by(|a, b| b.id.cmp(&a.id));
+    Ok(backups)
+}
+
+// `from` and `to` are backup ids, or "current" for the live file
+#[tauri::command]
+pub async fn diff_config_versions(file: String, from: String, to: String) -> Result<Vec<DiffLine>, String> {
+    let base_path = get_project_root()?;
+    managed_path(&base_path, &file)?;
+
+    let old = read_version(&base_path, &file, &from)?;
+    let new = read_version(&base_path, &file, &to)?;
+    Ok(diff_lines(&old, &new))
+}
+
+#[tauri::command]
+pub async fn restore_config_backup(file: String, id: String) -> Result<bool, String> {
+    let base_path = get_project_root()?;
+    let path = managed_path(&base_path, &file)?;
+    let content = read_version(&base_path, &file, &id)?;
+
+    // The version being replaced goes into the history too, so a restore can be undone
+    write_config_file(&base_path, &path, &content, &format!("Before restoring backup {}", id))?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshots_in_the_same_millisecond_are_all_kept() {
+        let base_path = std::env::temp_dir().join(format!("devstackbox-backups-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&base_path);
+        let path = base_path.join("config").join("httpd.conf");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+
+        let mut backups = Vec::new();
+        for content in ["Listen 80\n", "Listen 8080\n", "Listen 8081\n"] {
+            std::fs::write(&path, content).unwrap();
+            backups.push(snapshot(&base_path, &path, "test").unwrap().unwrap());
+        }
+
+        let ids: Vec<&str> = backups.iter().map(|backup| backup.id.as_str()).collect();
+        assert!(ids[0] != ids[1] && ids[1] != ids[2] && ids[0] != ids[2], "{:?}", ids);
+        assert_eq!(read_version(&base_path, "config/httpd.conf", ids[0]).unwrap(), "Listen 80\n");
+        assert_eq!(read_version(&base_path, "config/httpd.conf", ids[1]).unwrap(), "Listen 8080\n");
+        assert_eq!(read_version(&base_path, "config/httpd.conf", ids[2]).unwrap(), "Listen 8081\n");
+
+        // Newest first
+        let dir = history_dir(&base_path, "config/httpd.conf");
+        let history: Vec<String> = history(&dir).into_iter().map(|backup| backup.id).collect();
+        assert_eq!(history, [ids[2], ids[1], ids[0]]);
+        let _ = std::fs::remove_dir_all(&base_path);
+    }
+}
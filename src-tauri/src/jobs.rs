@@ -0,0 +1,232 @@
+// Long-running operations (installs, service starts, data directory init) run
+// as jobs. Each job gets an id, reports its phase and byte progress through
+// the "job-progress" event, and can be cancelled from the UI with cancel_job.
+// Cancellation is cooperative: the work checks its JobHandle at safe points.
+
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{LazyLock, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+use tokio::sync::watch;
+
+pub const PROGRESS_EVENT: &str = "job-progress";
+
+// Byte progress is emitted at most this often; phase and state changes always are
+const EMIT_INTERVAL: Duration = Duration::from_millis(100);
+
+// Finished jobs stay visible in list_jobs until this many newer ones finish
+const FINISHED_JOBS_KEPT: usize = 20;
+
+static APP_HANDLE: OnceLock<AppHandle> = OnceLock::new();
+static NEXT_JOB_ID: AtomicU64 = AtomicU64::new(1);
+static JOBS: LazyLock<Mutex<VecDeque<JobEntry>>> = LazyLock::new(|| Mutex::new(VecDeque::new()));
+
+#[derive(Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobState {
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Clone, Serialize)]
+pub struct Job {
+    pub id: u64,
+    // What kind of work this is ("php-install", "service-start", ...)
+    pub kind: String,
+    pub label: String,
+    pub state: JobState,
+    pub phase: String,
+    pub bytes: u64,
+    pub total_bytes: Option<u64>,
+    pub percent: Option<f64>,
+    pub error: Option<String>,
+    pub started_at: String,
+}
+
+struct JobEntry {
+    job: Job,
+    cancel: watch::Sender<bool>,
+    last_emit: Option<Instant>,
+}
+
+// Given to the work a job runs, for reporting progress and noticing cancellation
+#[derive(Clone)]
+pub struct JobHandle {
+    id: u64,
+    cancel: watch::Receiver<bool>,
+}
+
+// Called once from setup so jobs can emit events
+pub fn init(app: AppHandle) {
+    let _ = APP_HANDLE.set(app);
+}
+
+fn emit(job: &Job) {
+    emit_event(PROGRESS_EVENT, job.clone());
+}
+
+// For events outside jobs, which would otherwise each need the handle too
+pub(crate) fn emit_event<S: Serialize + Clone>(event: &str, payload: S) {
+    if let Some(app) = APP_HANDLE.get() {
+        let _ = app.emit(event, payload);
+    }
+}
+
+// Apply `change` to a job and emit the result, unless this is a throttled
+// progress update that comes too soon after the previous one
+fn update(id: u64, throttle: bool, change: impl FnOnce(&mut Job)) {
+    let snapshot = {
+        let mut jobs = match JOBS.lock() {
+            Ok(jobs) => jobs,
+            Err(_) => return,
+        };
+        let entry = match jobs.iter_mut().find(|entry| entry.job.id == id) {
+            Some(entry) => entry,
+            None => return,
+        };
+        change(&mut entry.job);
+
+        let due = entry.last_emit.is_none_or(|last| last.elapsed() >= EMIT_INTERVAL);
+        if throttle && !due {
+            return;
+        }
+        entry.last_emit = Some(Instant::now());
+        entry.job.clone()
+    };
+    emit(&snapshot);
+}
+
+impl JobHandle {
+    // Start a new phase; byte counters belong to a phase and are reset
+    pub fn phase(&self, phase: &str) {
+        update(self.id, false, |job| {
+            job.phase = phase.to_string();
+            job.bytes = 0;
+            job.total_bytes = None;
+            job.percent = None;
+        });
+    }
+
+    pub fn progress(&self, bytes: u64, total_bytes: Option<u64>) {
+        let done = total_bytes == Some(bytes);
+        update(self.id, !done, |job| {
+            job.bytes = bytes;
+            job.total_bytes = total_bytes;
+            job.percent = total_bytes
+                .filter(|&total| total > 0)
+                .map(|total| (bytes as f64 / total as f64 * 100.0).min(100.0));
+        });
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        *self.cancel.borrow()
+    }
+
+    // Err("Cancelled") once the user asked to cancel, for use with `?`
+    pub fn check_cancelled(&self) -> Result<(), String> {
+        if self.is_cancelled() {
+            Err("Cancelled".to_string())
+        } else {
+            Ok(())
+        }
+    }
+
+    // Resolves when the job is cancelled, for racing against slow awaits
+    pub async fn cancelled(&self) {
+        let mut cancel = self.cancel.clone();
+        let _ = cancel.wait_for(|&cancelled| cancelled).await;
+    }
+
+    // Run `work` until it finishes or the job is cancelled, whichever comes
+    // first; `work` is dropped on cancellation, so it must be safe to abandon
+    pub async fn cancellable<T>(&self, work: impl Future<Output = Result<T, String>>) -> Result<T, String> {
+        tokio::select! {
+            result = work => result,
+            _ = self.cancelled() => Err("Cancelled".to_string()),
+        }
+    }
+}
+
+// Run `work` as a job and return its result. The caller still gets the
+// outcome directly; the events are for progress display.
+pub async fn run<T, F, Fut>(kind: &str, label: String, work: F) -> Result<T, String>
+where
+    F: FnOnce(JobHandle) -> Fut,
+    Fut: Future<Output = Result<T, String>>,
+{
+    let id = NEXT_JOB_ID.fetch_add(1, Ordering::Relaxed);
+    let (cancel, cancel_receiver) = watch::channel(false);
+    let job = Job {
+        id,
+        kind: kind.to_string(),
+        label,
+        state: JobState::Running,
+        phase: "Starting".to_string(),
+        bytes: 0,
+        total_bytes: None,
+        percent: None,
+        error: None,
+        started_at: chrono::Local::now().to_rfc3339(),
+    };
+
+    {
+        let mut jobs = JOBS.lock().map_err(|e| e.to_string())?;
+        jobs.push_back(JobEntry { job: job.clone(), cancel, last_emit: Some(Instant::now()) });
+    }
+    emit(&job);
+
+    let handle = JobHandle { id, cancel: cancel_receiver };
+    let result = work(handle.clone()).await;
+
+    update(id, false, |job| {
+        job.state = match (&result, handle.is_cancelled()) {
+            (Ok(_), _) => JobState::Completed,
+            (Err(_), true) => JobState::Cancelled,
+            (Err(_), false) => JobState::Failed,
+        };
+        job.error = result.as_ref().err().cloned();
+    });
+    prune_finished();
+
+    result
+}
+
+fn prune_finished() {
+    if let Ok(mut jobs) = JOBS.lock() {
+        let finished = jobs.iter().filter(|entry| entry.job.state != JobState::Running).count();
+        let mut excess = finished.saturating_sub(FINISHED_JOBS_KEPT);
+        // Oldest first
+        jobs.retain(|entry| {
+            if excess > 0 && entry.job.state != JobState::Running {
+                excess -= 1;
+                false
+            } else {
+                true
+            }
+        });
+    }
+}
+
+#[tauri::command]
+pub async fn list_jobs() -> Result<Vec<Job>, String> {
+    let jobs = JOBS.lock().map_err(|e| e.to_string())?;
+    Ok(jobs.iter().map(|entry| entry.job.clone()).collect())
+}
+
+#[tauri::command]
+pub async fn cancel_job(id: u64) -> Result<bool, String> {
+    let jobs = JOBS.lock().map_err(|e| e.to_string())?;
+    match jobs.iter().find(|entry| entry.job.id == id) {
+        Some(entry) if entry.job.state == JobState::Running => {
+            entry.cancel.send_replace(true);
+            Ok(true)
+        }
+        Some(_) => Err(format!("Job {} has already finished", id)),
+        None => Err(format!("No job with id {}", id)),
+    }
+}
@@ -1,13 +1,39 @@
 use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
-use std::sync::LazyLock;
-use std::process::Command;
 use std::path::{Path, PathBuf};
-use std::time::Duration;
-use tokio::time::sleep;
+
+mod backups;
+mod jobs;
+mod logs;
+mod php;
+mod platform;
+mod process;
+mod services;
+mod settings;
+mod shutdown;
+mod templates;
+
+use services::ServiceInfo;
+
+// Root the tests run the app against, instead of moving the whole test
+// process to another working directory
+#[cfg(test)]
+static TEST_ROOT: std::sync::RwLock<Option<PathBuf>> = std::sync::RwLock::new(None);
+
+#[cfg(test)]
+fn set_test_root(root: Option<PathBuf>) {
+    match TEST_ROOT.write() {
+        Ok(mut current) => *current = root,
+        Err(poisoned) => *poisoned.into_inner() = root,
+    }
+}
 
 // Helper function to get the project root directory
 fn get_project_root() -> Result<PathBuf, String> {
+    #[cfg(test)]
+    if let Some(root) = TEST_ROOT.read().ok().and_then(|root| root.clone()) {
+        return Ok(root);
+    }
+
     let current_dir = std::env::current_dir().map_err(|e| e.to_string())?;
     if current_dir.file_name().and_then(|name| name.to_str()) == Some("src-tauri") {
         // If we're in src-tauri directory, go up one level to DevStackBox
@@ -18,92 +44,70 @@ fn get_project_root() -> Result<PathBuf, String> {
     }
 }
 
-// Service status and process tracking
-static SERVICE_STATUS: LazyLock<Arc<Mutex<HashMap<String, bool>>>> = 
-    LazyLock::new(|| Arc::new(Mutex::new(HashMap::new())));
-
-static SERVICE_PROCESSES: LazyLock<Arc<Mutex<HashMap<String, u32>>>> = 
-    LazyLock::new(|| Arc::new(Mutex::new(HashMap::new())));
-
-#[derive(serde::Serialize)]
-struct ServiceInfo {
-    running: bool,
-    pid: Option<u32>,
-    port: Option<u16>,
-    version: Option<String>,
+fn php_binary(php_dir: &Path) -> Option<PathBuf> {
+    platform::find_executable(php_dir, "php")
 }
 
 #[derive(serde::Serialize)]
 struct PHPVersionInfo {
     version: String,
+    branch: String,
     status: String, // "installed", "available", "downloading"
     path: String,
     is_active: bool,
     installed: bool,
     download_url: String,
+    // Newer patch release of the same branch, for installed versions
+    update_available: Option<String>,
+    // Every build the releases index offers (thread safety, architecture)
+    builds: Vec<php::releases::PhpBuild>,
 }
 
 #[tauri::command]
 async fn check_binaries() -> Result<HashMap<String, bool>, String> {
     let mut binaries = HashMap::new();
-    
+
     // Get the project root directory (DevStackBox)
-    let current_dir = std::env::current_dir().map_err(|e| e.to_string())?;
-    let base_path = if current_dir.file_name().and_then(|name| name.to_str()) == Some("src-tauri") {
-        // If we're in src-tauri directory, go up one level to DevStackBox
-        current_dir.parent().unwrap_or(&current_dir)
-    } else {
-        // If we're already in DevStackBox or elsewhere, use current directory
-        &current_dir
-    };
-    
-    // Check MySQL
-    let mysql_path = base_path.join("mysql").join("bin").join("mysqld.exe");
-    binaries.insert("mysql".to_string(), mysql_path.exists());
-    
-    // Check Apache
-    let apache_path = base_path.join("apache").join("bin").join("httpd.exe");
-    binaries.insert("apache".to_string(), apache_path.exists());
-    
+    let base_path = get_project_root()?;
+
+    // Check MySQL, Apache and any other registered service
+    for service in services::registry() {
+        binaries.insert(service.name().to_string(), service.binary(&base_path).is_some());
+    }
+
     // Check PHP 8.2
-    let php_path = base_path.join("php").join("8.2").join("php.exe");
-    binaries.insert("php8.2".to_string(), php_path.exists());
-    
+    binaries.insert("php8.2".to_string(), php_binary(&base_path.join("php").join("8.2")).is_some());
+
     Ok(binaries)
 }
 
 #[tauri::command]
 async fn debug_paths() -> Result<HashMap<String, String>, String> {
     let mut paths = HashMap::new();
-    
+
     // Get the project root directory (DevStackBox)
     let current_dir = std::env::current_dir().map_err(|e| e.to_string())?;
-    let base_path = if current_dir.file_name().and_then(|name| name.to_str()) == Some("src-tauri") {
-        // If we're in src-tauri directory, go up one level to DevStackBox
-        current_dir.parent().unwrap_or(&current_dir)
-    } else {
-        // If we're already in DevStackBox or elsewhere, use current directory
-        &current_dir
-    };
-    
+    let base_path = get_project_root()?;
+
     paths.insert("current_dir".to_string(), current_dir.display().to_string());
     paths.insert("base_path".to_string(), base_path.display().to_string());
-    
-    // Check MySQL
-    let mysql_path = base_path.join("mysql").join("bin").join("mysqld.exe");
-    paths.insert("mysql_path".to_string(), mysql_path.display().to_string());
-    paths.insert("mysql_exists".to_string(), mysql_path.exists().to_string());
-    
-    // Check Apache
-    let apache_path = base_path.join("apache").join("bin").join("httpd.exe");
-    paths.insert("apache_path".to_string(), apache_path.display().to_string());
-    paths.insert("apache_exists".to_string(), apache_path.exists().to_string());
-    
+    paths.insert("platform".to_string(), std::env::consts::OS.to_string());
+
+    // Check MySQL, Apache and any other registered service
+    for service in services::registry() {
+        let binary = service.binary(&base_path);
+        let display = binary.clone().unwrap_or_else(|| service.expected_binary(&base_path));
+        paths.insert(format!("{}_path", service.name()), display.display().to_string());
+        paths.insert(format!("{}_exists", service.name()), binary.is_some().to_string());
+    }
+
     // Check PHP 8.2
-    let php_path = base_path.join("php").join("8.2").join("php.exe");
-    paths.insert("php_path".to_string(), php_path.display().to_string());
-    paths.insert("php_exists".to_string(), php_path.exists().to_string());
-    
+    let php_dir = base_path.join("php").join("8.2");
+    let php_path = php_binary(&php_dir);
+    let php_display = php_path.clone().unwrap_or_else(|| php_dir.join(platform::exe_name("php")));
+    paths.insert("php_path".to_string(), php_display.display().to_string());
+    paths.insert("php_exists".to_string(), php_path.is_some().to_string());
+
     Ok(paths)
 }
 
@@ -112,289 +116,69 @@ fn greet(name: &str) -> String {
     format!("Hello, {}! You've been greeted from Rust!", name)
 }
 
-#[tauri::command]
-async fn get_mysql_status() -> Result<ServiceInfo, String> {
-    let running = {
-        let status = SERVICE_STATUS.lock().map_err(|e| e.to_string())?;
-        status.get("mysql").unwrap_or(&false).clone()
-    };
-    
-    let pid = if running {
-        let processes = SERVICE_PROCESSES.lock().map_err(|e| e.to_string())?;
-        processes.get("mysql").cloned()
-    } else {
-        None
-    };
-
-    Ok(ServiceInfo {
-        running,
-        pid,
-        port: Some(3306),
-        version: get_mysql_version().await,
-    })
-}
-
-async fn initialize_mysql_data() -> Result<(), String> {
-    let current_dir = std::env::current_dir().map_err(|e| e.to_string())?;
-    let base_path = if current_dir.file_name().and_then(|name| name.to_str()) == Some("src-tauri") {
-        // If we're in src-tauri directory, go up one level to DevStackBox
-        current_dir.parent().unwrap_or(&current_dir)
-    } else {
-        // If we're already in DevStackBox or elsewhere, use current directory
-        &current_dir
-    };
-    
-    let data_dir = base_path.join("mysql").join("data");
-    let mysql_bin_path = base_path.join("mysql").join("bin").join("mysqld.exe");
-    
-    // Check if data directory is empty or missing mysql system tables
-    let mysql_dir = data_dir.join("mysql");
-    if !mysql_dir.exists() {
-        // Initialize MySQL data directory
-        match Command::new(&mysql_bin_path)
-            .arg("--initialize-insecure")
-            .arg(format!("--basedir={}", base_path.join("mysql").display()))
-            .arg(format!("--datadir={}", data_dir.display()))
-            .output()
-        {
-            Ok(_) => {
-                println!("MySQL data directory initialized successfully");
-                Ok(())
-            }
-            Err(e) => Err(format!("Failed to initialize MySQL data directory: {}", e))
-        }
-    } else {
-        Ok(())
-    }
-}
-
-#[tauri::command]
-async fn start_mysql() -> Result<bool, String> {
-    // Get the project root directory (DevStackBox)
-    let current_dir = std::env::current_dir().map_err(|e| e.to_string())?;
-    let base_path = if current_dir.file_name().and_then(|name| name.to_str()) == Some("src-tauri") {
-        // If we're in src-tauri directory, go up one level to DevStackBox
-        current_dir.parent().unwrap_or(&current_dir)
-    } else {
-        // If we're already in DevStackBox or elsewhere, use current directory
-        &current_dir
-    };
-    
-    let mysql_path = base_path.join("mysql").join("bin").join("mysqld.exe");
-    if !mysql_path.exists() {
-        return Err(format!("MySQL binary not found at {}. Please ensure MySQL is installed.", mysql_path.display()));
-    }
-
-    let config_path = base_path.join("config").join("my.cnf");
-    if !config_path.exists() {
-        create_default_mysql_config().await?;
-    }
-
-    // Initialize MySQL data directory if needed
-    initialize_mysql_data().await?;
-
-    match Command::new(&mysql_path)
-        .arg(format!("--defaults-file={}", config_path.display()))
-        .arg("--console")
-        .spawn()
-    {
-        Ok(child) => {
-            let pid = child.id();
-            
-            // Wait a moment for MySQL to start
-            sleep(Duration::from_secs(5)).await;
-            
-            // Verify MySQL is actually running by checking port 3306
-            match std::process::Command::new("netstat")
-                .arg("-ano")
-                .output()
-            {
-                Ok(netstat_output) => {
-                    let output_str = String::from_utf8_lossy(&netstat_output.stdout);
-                    if output_str.contains(":3306 ") {
-                        // Update service status
-                        {
-                            let mut status = SERVICE_STATUS.lock().map_err(|e| e.to_string())?;
-                            status.insert("mysql".to_string(), true);
-                        }
-                        
-                        // Store process PID
-                        {
-                            let mut processes = SERVICE_PROCESSES.lock().map_err(|e| e.to_string())?;
-                            processes.insert("mysql".to_string(), pid);
-                        }
-                        
-                        Ok(true)
-                    } else {
-                        Err("MySQL started but port 3306 is not listening".to_string())
-                    }
-                }
-                Err(_) => {
-                    Err("Failed to verify MySQL is running".to_string())
-                }
-            }
-        }
-        Err(e) => Err(format!("Failed to start MySQL: {}", e)),
-    }
-}
-
-#[tauri::command]
-async fn stop_mysql() -> Result<bool, String> {
-    // Get the PID
-    let pid = {
-        let processes = SERVICE_PROCESSES.lock().map_err(|e| e.to_string())?;
-        processes.get("mysql").cloned()
-    };
-
-    if let Some(pid) = pid {
-        // Kill the process on Windows
-        match Command::new("taskkill")
-            .arg("/F")
-            .arg("/PID")
-            .arg(&pid.to_string())
-            .output()
-        {
-            Ok(_) => {
-                // Update service status
-                {
-                    let mut status = SERVICE_STATUS.lock().map_err(|e| e.to_string())?;
-                    status.insert("mysql".to_string(), false);
-                }
-                
-                // Remove from process tracking
-                {
-                    let mut processes = SERVICE_PROCESSES.lock().map_err(|e| e.to_string())?;
-                    processes.remove("mysql");
-                }
-                
-                Ok(true)
-            }
-            Err(e) => Err(format!("Failed to stop MySQL: {}", e)),
-        }
-    } else {
-        Err("MySQL is not running".to_string())
-    }
-}
-
-async fn create_default_mysql_config() -> Result<(), String> {
-    let base_path = get_project_root()?;
-    
-    let mysql_base = base_path.join("mysql");
-    let mysql_data = base_path.join("mysql").join("data");
-    
-    let config_content = format!(r#"[mysqld]
-port=3306
-basedir={}
-datadir={}
-default-storage-engine=InnoDB
-sql-mode="STRICT_TRANS_TABLES,NO_ZERO_DATE,NO_ZERO_IN_DATE,ERROR_FOR_DIVISION_BY_ZERO"
-max_connections=100
-table_open_cache=2000
-tmp_table_size=16M
-thread_cache_size=10
-key_buffer_size=8M
-sort_buffer_size=256K
-skip-networking=false
-bind-address=127.0.0.1
-
-[mysql]
-default-character-set=utf8mb4
-
-[client]
-port=3306
-default-character-set=utf8mb4
-"#, mysql_base.display().to_string().replace("\\", "/"), mysql_data.display().to_string().replace("\\", "/"));
-
-    let config_dir = base_path.join("config");
-    std::fs::create_dir_all(&config_dir).map_err(|e| e.to_string())?;
-    std::fs::write(config_dir.join("my.cnf"), config_content).map_err(|e| e.to_string())?;
-    Ok(())
-}
-
 async fn check_active_php_version(version: &str) -> bool {
     // Check if this version is currently active by checking the symlink
-    let current_path = Path::new("php/current");
+    let current_path = match get_project_root() {
+        Ok(base_path) => base_path.join("php").join("current"),
+        Err(_) => return false,
+    };
     if current_path.exists() {
-        if let Ok(target) = std::fs::read_link(current_path) {
-            if let Some(target_str) = target.to_str() {
-                return target_str.contains(version);
-            }
+        if let Ok(target) = std::fs::read_link(&current_path) {
+            return target.file_name().and_then(|name| name.to_str()) == Some(version);
         }
     }
     false
 }
 
-async fn update_php_config(version: &str) -> Result<(), String> {
-    // Update Apache config to use the new PHP version
-    let apache_config_path = "config/httpd.conf";
-    if Path::new(apache_config_path).exists() {
-        let content = std::fs::read_to_string(apache_config_path).map_err(|e| e.to_string())?;
-        let updated_content = content.replace(
-            "php/php8apache2_4.dll",
-            &format!("php/current/php{}apache2_4.dll", version.replace(".", ""))
-        );
-        std::fs::write(apache_config_path, updated_content).map_err(|e| e.to_string())?;
-    }
-    Ok(())
-}
-
-async fn get_mysql_version() -> Option<String> {
-    let base_path = get_project_root().ok()?;
-    let mysql_path = base_path.join("mysql").join("bin").join("mysqld.exe");
-    
-    if !mysql_path.exists() {
-        return None;
-    }
-
-    match Command::new(&mysql_path)
-        .arg("--version")
-        .output()
-    {
-        Ok(output) => {
-            let version_str = String::from_utf8_lossy(&output.stdout);
-            // Extract version from output like "mysqld  Ver 8.0.35 for Win64 on x86_64"
-            if let Some(start) = version_str.find("Ver ") {
-                if let Some(end) = version_str[start + 4..].find(" ") {
-                    return Some(version_str[start + 4..start + 4 + end].to_string());
-                }
-            }
-            None
-        }
-        Err(_) => None,
-    }
+#[derive(serde::Serialize)]
+struct PhpStatus {
+    #[serde(flatten)]
+    info: ServiceInfo,
+    // xdebug.mode of the active version ("debug", "debug,profile"), None without Xdebug
+    xdebug_mode: Option<String>,
 }
 
 #[tauri::command]
-async fn get_php_status() -> Result<ServiceInfo, String> {
+async fn get_php_status() -> Result<PhpStatus, String> {
     // PHP doesn't run as a service, so we check if it's available
     let version = get_current_php_version().await;
-    
-    Ok(ServiceInfo {
-        running: version.is_some(),
-        pid: None,
-        port: None,
-        version,
+
+    Ok(PhpStatus {
+        info: ServiceInfo {
+            running: version.is_some(),
+            pid: None,
+            port: None,
+            version,
+        },
+        xdebug_mode: php::xdebug::active_mode(&get_project_root()?),
     })
 }
 
 async fn get_current_php_version() -> Option<String> {
-    let php_path = Path::new("php/current/php.exe");
-    if !php_path.exists() {
-        // Check default PHP 8.2
-        let default_php = Path::new("php/8.2/php.exe");
-        if default_php.exists() {
-            return Some("8.2".to_string());
+    let php_root = get_project_root().ok()?.join("php");
+    let php_path = match php_binary(&php_root.join("current")) {
+        Some(path) => path,
+        None => {
+            // Fall back to the version selected in settings
+            let base_path = get_project_root().ok()?;
+            let version = settings::load(&base_path).ok()?.php.active_version;
+            if php_binary(&php_root.join(&version)).is_some() {
+                return Some(version);
+            }
+            return None;
         }
-        return None;
-    }
+    };
 
     // Get version from current PHP
-    match Command::new("php/current/php.exe")
-        .arg("--version")
-        .output()
-    {
+    php_version_of(&php_path).await
+}
+
+// "PHP 8.3.14 (cli) ..." -> "8.3.14"
+async fn php_version_of(php_path: &Path) -> Option<String> {
+    let cmd = process::Cmd::new(php_path).arg("--version");
+    match process::runner().output(&cmd, services::VERSION_TIMEOUT).await {
         Ok(output) => {
-            let version_str = String::from_utf8_lossy(&output.stdout);
+            let version_str = output.stdout;
             if let Some(start) = version_str.find("PHP ") {
                 if let Some(end) = version_str[start + 4..].find(" ") {
                     return Some(version_str[start + 4..start + 4 + end].to_string());
@@ -406,357 +190,153 @@ async fn get_current_php_version() -> Option<String> {
     }
 }
 
+// PHP versions installed under php/, directory name and binary, newest first
+fn installed_php_versions(php_root: &Path) -> Vec<(String, PathBuf)> {
+    let mut installed: Vec<(String, PathBuf)> = std::fs::read_dir(php_root)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_str()?.to_string();
+            // php/current is a link to one of the others, dot directories are ours
+            if name == "current" || name.starts_with('.') {
+                return None;
+            }
+            Some((name, php_binary(&entry.path())?))
+        })
+        .collect();
+    installed.sort_by(|a, b| php::releases::compare_versions(&b.0, &a.0));
+    installed
+}
+
+// `refresh` re-downloads the releases index even if the cached copy is fresh
 #[tauri::command]
-async fn get_php_versions() -> Result<Vec<PHPVersionInfo>, String> {
+async fn get_php_versions(refresh: Option<bool>) -> Result<Vec<PHPVersionInfo>, String> {
     let mut versions = Vec::new();
-    
-    // Check for installed PHP versions
-    for version in &["8.1", "8.2", "8.3", "8.4"] {
-        let php_path = format!("php/{}/php.exe", version);
-        let installed = Path::new(&php_path).exists();
-        
-        let active = if installed {
-            check_active_php_version(version).await
+    let base_path = get_project_root()?;
+    let php_root = base_path.join("php");
+    let php_settings = settings::load(&base_path)?.php;
+
+    // Without an index (offline, nothing cached) installed versions are still listed
+    let releases = match php::releases::load_index(&base_path, &php_settings, refresh.unwrap_or(false)).await {
+        Ok(index) => {
+            if index.stale {
+                println!("Using cached PHP releases index, the configured one could not be fetched");
+            }
+            index.releases
+        }
+        Err(e) => {
+            println!("PHP releases index unavailable: {}", e);
+            Vec::new()
+        }
+    };
+
+    let installed = installed_php_versions(&php_root);
+    for (version, php_path) in &installed {
+        // Older installs are named after the branch ("8.2"); ask the binary for the patch
+        let patch = if version.split('.').count() >= 3 {
+            Some(version.clone())
         } else {
-            false
+            php_version_of(php_path).await
         };
 
         versions.push(PHPVersionInfo {
-            version: version.to_string(),
-            status: if installed { "installed".to_string() } else { "available".to_string() },
-            path: php_path,
-            is_active: active,
-            installed,
-            download_url: format!("https://windows.php.net/downloads/releases/php-{}-Win32-vs16-x64.zip", version),
+            version: version.clone(),
+            branch: php::releases::branch_of(version),
+            status: "installed".to_string(),
+            path: php_path.display().to_string(),
+            is_active: check_active_php_version(version).await,
+            installed: true,
+            download_url: String::new(),
+            update_available: patch.and_then(|patch| php::releases::newer_patch(&releases, &patch)),
+            builds: Vec::new(),
         });
     }
-    
-    Ok(versions)
-}
-
-#[tauri::command]
-async fn switch_php_version(version: String) -> Result<bool, String> {
-    let php_path = format!("php/{}/php.exe", version);
-    if !Path::new(&php_path).exists() {
-        return Err(format!("PHP {} is not installed", version));
-    }
-
-    // Create symlink or copy to main php directory
-    let main_php_dir = Path::new("php/current");
-    let version_php_dir = format!("php/{}", version);
 
-    // Remove existing current directory
-    if main_php_dir.exists() {
-        std::fs::remove_dir_all(main_php_dir).map_err(|e| e.to_string())?;
-    }
+    let arch = php::releases::host_arch();
+    for release in releases.iter().filter(|release| !installed.iter().any(|(name, _)| *name == release.version)) {
+        let download_url = match php::releases::select_build(release, php_settings.thread_safe, arch) {
+            Some(build) => build.url.clone(),
+            None => php::install::download_url(&php_settings.mirror, &release.version, php_settings.thread_safe, arch),
+        };
 
-    // Create junction point on Windows (similar to symlink)
-    match Command::new("mklink")
-        .arg("/J")
-        .arg("php\\current")
-        .arg(&version_php_dir)
-        .output()
-    {
-        Ok(_) => {
-            // Update PHP configuration to point to current version
-            update_php_config(&version).await?;
-            Ok(true)
-        }
-        Err(e) => Err(format!("Failed to switch PHP version: {}", e)),
+        versions.push(PHPVersionInfo {
+            version: release.version.clone(),
+            branch: release.branch.clone(),
+            status: "available".to_string(),
+            path: php_root.join(&release.version).join(platform::exe_name("php")).display().to_string(),
+            is_active: false,
+            installed: false,
+            download_url,
+            update_available: None,
+            builds: release.builds.clone(),
+        });
     }
-}
-
-#[tauri::command]
-async fn download_php_version(version: String) -> Result<bool, String> {
-    // This is a placeholder - in real implementation, you would:
-    // 1. Download the PHP zip from the URL
-    // 2. Extract it to php/{version}/ directory
-    // 3. Configure it properly
-    
-    // For now, simulate download
-    sleep(Duration::from_secs(3)).await;
-    
-    // Create directory structure
-    let php_dir = format!("php/{}", version);
-    std::fs::create_dir_all(&php_dir).map_err(|e| e.to_string())?;
-    
-    // Create a placeholder php.exe (in real implementation, this would be the actual binary)
-    let php_exe = format!("{}/php.exe", php_dir);
-    std::fs::write(&php_exe, "placeholder").map_err(|e| e.to_string())?;
-    
-    Ok(true)
-}
-
-#[tauri::command]
-async fn get_apache_status() -> Result<ServiceInfo, String> {
-    let running = {
-        let status = SERVICE_STATUS.lock().map_err(|e| e.to_string())?;
-        status.get("apache").unwrap_or(&false).clone()
-    };
-    
-    let pid = if running {
-        let processes = SERVICE_PROCESSES.lock().map_err(|e| e.to_string())?;
-        processes.get("apache").cloned()
-    } else {
-        None
-    };
 
-    Ok(ServiceInfo {
-        running,
-        pid,
-        port: Some(80),
-        version: get_apache_version().await,
-    })
+    Ok(versions)
 }
 
 #[tauri::command]
-async fn start_apache() -> Result<bool, String> {
-    // Get the project root directory (DevStackBox)
+async fn switch_php_version(version: String) -> Result<bool, String> {
     let base_path = get_project_root()?;
-    
-    let apache_path = base_path.join("apache").join("bin").join("httpd.exe");
-    if !apache_path.exists() {
-        return Err(format!("Apache binary not found at {}. Please ensure Apache is installed.", apache_path.display()));
-    }
+    // Links php/current, rewires httpd.conf and php.ini, rolls back if Apache rejects it
+    php::switch::switch(&base_path, &version).await?;
 
-    let config_path = base_path.join("config").join("httpd.conf");
-    if !config_path.exists() {
-        create_default_apache_config().await?;
-    }
-
-    // Change to base directory before starting Apache
-    std::env::set_current_dir(&base_path).map_err(|e| e.to_string())?;
-
-    // Test Apache configuration first
-    match Command::new(&apache_path)
-        .arg("-f")
-        .arg(&config_path)
-        .arg("-t")
-        .output()
-    {
-        Ok(output) => {
-            if !output.status.success() {
-                let error = String::from_utf8_lossy(&output.stderr);
-                return Err(format!("Apache configuration test failed: {}", error));
-            }
-        }
-        Err(e) => return Err(format!("Failed to test Apache configuration: {}", e)),
-    }
+    let mut settings = settings::load(&base_path)?;
+    settings.php.active_version = version;
+    settings::save(&base_path, &settings)?;
 
-    // Now try to start Apache
-    match Command::new(&apache_path)
-        .arg("-f")
-        .arg(&config_path)
-        .arg("-D")
-        .arg("FOREGROUND")
-        .spawn()
-    {
-        Ok(child) => {
-            let pid = child.id();
-            
-            // Wait a moment for Apache to start
-            sleep(Duration::from_secs(3)).await;
-            
-            // Verify Apache is actually running by checking port 80
-            match std::process::Command::new("netstat")
-                .arg("-ano")
-                .output()
-            {
-                Ok(netstat_output) => {
-                    let output_str = String::from_utf8_lossy(&netstat_output.stdout);
-                    if output_str.contains(":80 ") {
-                        // Update service status
-                        {
-                            let mut status = SERVICE_STATUS.lock().map_err(|e| e.to_string())?;
-                            status.insert("apache".to_string(), true);
-                        }
-                        
-                        // Store process PID
-                        {
-                            let mut processes = SERVICE_PROCESSES.lock().map_err(|e| e.to_string())?;
-                            processes.insert("apache".to_string(), pid);
-                        }
-                        
-                        Ok(true)
-                    } else {
-                        Err("Apache started but port 80 is not listening".to_string())
-                    }
-                }
-                Err(_) => {
-                    // If we can't verify, assume it started
-                    {
-                        let mut status = SERVICE_STATUS.lock().map_err(|e| e.to_string())?;
-                        status.insert("apache".to_string(), true);
-                    }
-                    
-                    {
-                        let mut processes = SERVICE_PROCESSES.lock().map_err(|e| e.to_string())?;
-                        processes.insert("apache".to_string(), pid);
-                    }
-                    
-                    Ok(true)
-                }
-            }
-        }
-        Err(e) => Err(format!("Failed to start Apache: {}", e)),
-    }
+    // A running Apache still has the old module loaded
+    services::reload_if_running(services::find_service("apache")?).await?;
+    Ok(true)
 }
 
+// `version` is a patch ("8.3.14") or a branch ("8.3", meaning its latest
+// patch). `sha256` overrides the index or published checksum; `thread_safe`
+// and `arch` default to the settings and the host architecture.
 #[tauri::command]
-async fn stop_apache() -> Result<bool, String> {
-    // Get the PID
-    let pid = {
-        let processes = SERVICE_PROCESSES.lock().map_err(|e| e.to_string())?;
-        processes.get("apache").cloned()
-    };
-
-    if let Some(pid) = pid {
-        // Kill the process on Windows
-        match Command::new("taskkill")
-            .arg("/F")
-            .arg("/PID")
-            .arg(&pid.to_string())
-            .output()
-        {
-            Ok(_) => {
-                // Update service status
-                {
-                    let mut status = SERVICE_STATUS.lock().map_err(|e| e.to_string())?;
-                    status.insert("apache".to_string(), false);
-                }
-                
-                // Remove from process tracking
-                {
-                    let mut processes = SERVICE_PROCESSES.lock().map_err(|e| e.to_string())?;
-                    processes.remove("apache");
-                }
-                
-                Ok(true)
-            }
-            Err(e) => Err(format!("Failed to stop Apache: {}", e)),
-        }
-    } else {
-        Err("Apache is not running".to_string())
-    }
-}
-
-async fn create_default_apache_config() -> Result<(), String> {
+async fn download_php_version(
+    version: String,
+    sha256: Option<String>,
+    thread_safe: Option<bool>,
+    arch: Option<String>,
+) -> Result<bool, String> {
+    php::install::check_platform()?;
     let base_path = get_project_root()?;
-    
-    let apache_root = base_path.join("apache");
-    let www_root = base_path.join("www");
-    
-    let config_content = format!(r#"# Apache Configuration for DevStackBox
-ServerRoot "{}"
-PidFile "{}/logs/httpd.pid"
-Listen 80
-
-# Essential modules
-LoadModule dir_module modules/mod_dir.so
-LoadModule mime_module modules/mod_mime.so
-LoadModule rewrite_module modules/mod_rewrite.so
-LoadModule authz_core_module modules/mod_authz_core.so
-LoadModule authz_host_module modules/mod_authz_host.so
-LoadModule access_compat_module modules/mod_access_compat.so
-
-ServerName localhost:80
-DocumentRoot "{}"
-
-<Directory "{}">
-    Options Indexes FollowSymLinks
-    AllowOverride All
-    Require all granted
-    DirectoryIndex index.html index.htm index.php
-</Directory>
-
-# MIME Types
-TypesConfig conf/mime.types
-AddType text/html .html .htm
-
-# Error and Access logs
-ErrorLog "{}/logs/error.log"
-CustomLog "{}/logs/access.log" common
-
-# Security
-ServerTokens Prod
-ServerSignature Off
-"#, 
-    apache_root.display().to_string().replace("\\", "/"),
-    base_path.display().to_string().replace("\\", "/"),
-    www_root.display().to_string().replace("\\", "/"),
-    www_root.display().to_string().replace("\\", "/"),
-    base_path.display().to_string().replace("\\", "/"),
-    base_path.display().to_string().replace("\\", "/")
-    );
-
-    let config_dir = base_path.join("config");
-    std::fs::create_dir_all(&config_dir).map_err(|e| e.to_string())?;
-    std::fs::write(config_dir.join("httpd.conf"), config_content).map_err(|e| e.to_string())?;
-    Ok(())
-}
-
-async fn get_apache_version() -> Option<String> {
-    let current_dir = std::env::current_dir().ok()?;
-    let base_path = current_dir.parent().unwrap_or(&current_dir);
-    let apache_path = base_path.join("apache").join("bin").join("httpd.exe");
-    
-    if !apache_path.exists() {
-        return None;
-    }
-
-    match Command::new(&apache_path)
-        .arg("-v")
-        .output()
-    {
-        Ok(output) => {
-            let version_str = String::from_utf8_lossy(&output.stdout);
-            if let Some(start) = version_str.find("Apache/") {
-                if let Some(end) = version_str[start + 7..].find(" ") {
-                    return Some(version_str[start + 7..start + 7 + end].to_string());
-                }
-            }
-            None
+    let php_settings = settings::load(&base_path)?.php;
+    let thread_safe = thread_safe.unwrap_or(php_settings.thread_safe);
+    let arch = arch.unwrap_or_else(|| php::releases::host_arch().to_string());
+
+    // The index knows the exact file name and checksum; fall back to the
+    // release naming scheme for versions it does not list
+    let releases = php::releases::load_index(&base_path, &php_settings, false)
+        .await
+        .map(|index| index.releases)
+        .unwrap_or_default();
+    let indexed = php::releases::find_release(&releases, &version)
+        .and_then(|release| Some((release, php::releases::select_build(release, thread_safe, &arch)?)));
+    let (version, url, sha256) = match indexed {
+        Some((release, build)) => (release.version.clone(), build.url.clone(), sha256.or_else(|| build.sha256.clone())),
+        None => {
+            let url = php::install::download_url(&php_settings.mirror, &version, thread_safe, &arch);
+            (version, url, sha256)
         }
-        Err(_) => None,
-    }
-}
-
-// Toggle functions for frontend compatibility
-#[tauri::command]
-async fn toggle_mysql() -> Result<bool, String> {
-    let status = get_mysql_status().await?;
-    if status.running {
-        stop_mysql().await?;
-        Ok(false)
-    } else {
-        start_mysql().await?;
-        Ok(true)
-    }
-}
+    };
 
-#[tauri::command]
-async fn toggle_apache() -> Result<bool, String> {
-    let status = get_apache_status().await?;
-    if status.running {
-        stop_apache().await?;
-        Ok(false)
-    } else {
-        start_apache().await?;
-        Ok(true)
-    }
+    jobs::run("php-install", format!("Installing PHP {}", version), |job| async move {
+        php::install::install(&base_path, &version, &url, sha256, &job).await
+    })
+    .await?;
+    Ok(true)
 }
 
+// Toggle function for frontend compatibility
 #[tauri::command]
 async fn toggle_php() -> Result<bool, String> {
     // PHP doesn't start/stop like a service, just return a status
     Ok(true)
 }
 
-#[tauri::command]
-async fn get_service_logs(service: String) -> Result<String, String> {
-    Ok(format!("Logs for {} service:\n\nService started successfully\nNo errors reported\n\n[This is a placeholder log]", service))
-}
-
 #[tauri::command]
 async fn create_directory_structure() -> Result<String, String> {
     let current_dir = std::env::current_dir().map_err(|e| e.to_string())?;
@@ -1021,26 +601,67 @@ pub fn run() {
             greet,
             check_binaries,
             debug_paths,
-            get_mysql_status,
-            get_php_status, 
-            get_apache_status,
-            start_mysql,
-            stop_mysql,
-            start_apache,
-            stop_apache,
+            services::start_service,
+            services::stop_service,
+            services::restart_service,
+            services::reload_service,
+            services::profiles::start_profile,
+            services::profiles::stop_profile,
+            services::service_status,
+            services::list_services,
+            services::ports::check_service_port,
+            services::ports::check_service_ports,
+            services::ports::set_service_port,
+            settings::get_settings,
+            settings::update_settings,
+            services::config::preview_config,
+            services::config::regenerate_config,
+            backups::list_config_backups,
+            backups::diff_config_versions,
+            backups::restore_config_backup,
+            jobs::list_jobs,
+            jobs::cancel_job,
+            get_php_status,
             get_php_versions,
             switch_php_version,
+            php::projects::get_project_php_versions,
+            php::projects::switch_project_php_version,
+            php::extensions::list_php_extensions,
+            php::extensions::set_php_extension,
+            php::ini::get_php_ini,
+            php::ini::set_php_ini_directive,
+            php::ini::list_php_ini_presets,
+            php::ini::apply_php_ini_preset,
+            php::xdebug::get_xdebug_status,
+            php::xdebug::setup_xdebug,
+            php::xdebug::set_xdebug_mode,
             download_php_version,
-            toggle_mysql,
             toggle_php,
-            toggle_apache,
-            get_service_logs,
+            logs::list_service_logs,
+            logs::get_service_logs,
+            logs::parse::query_logs,
+            logs::rotate::rotate_service_logs,
+            logs::rotate::clear_service_logs,
+            logs::tail::subscribe_logs,
+            logs::tail::unsubscribe_logs,
+            shutdown::quit_app,
+            shutdown::cancel_quit,
             create_directory_structure
         ])
-        .setup(|_app| {
+        .setup(|app| {
+            jobs::init(app.handle().clone());
+            // Servers left running by an earlier run of the app
+            tauri::async_runtime::block_on(services::reconcile::reconcile());
+            tauri::async_runtime::spawn(services::profiles::auto_start());
+            logs::rotate::init();
             println!("DevStackBox setup complete, window should be opening...");
             Ok(())
         })
+        .on_window_event(|window, event| {
+            if let tauri::WindowEvent::CloseRequested { api, .. } = event {
+                shutdown::on_close_requested(window, api);
+            }
+        })
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
@@ -0,0 +1,125 @@
+// Console output of the servers we spawn. stdout and stderr are piped into
+// logs/<service>.log by a reader task per stream; the file is rotated by
+// size as it is written so a chatty server cannot fill the disk, and by age
+// through the rotation sweep, which asks the writer to do it.
+
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock, Mutex, Weak};
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+use super::rotate::{self, Policy};
+use crate::process::{OutputStream, Process};
+
+// Writers of running services by log path; gone once the process exits
+static WRITERS: LazyLock<Mutex<HashMap<PathBuf, Weak<Mutex<RotatingFile>>>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+pub(crate) struct RotatingFile {
+    path: PathBuf,
+    // None only while rotating; Windows cannot rename a file we hold open
+    file: Option<File>,
+    size: u64,
+    policy: Policy,
+}
+
+impl RotatingFile {
+    pub(crate) fn open(path: &Path, policy: Policy) -> io::Result<RotatingFile> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let size = file.metadata()?.len();
+        Ok(RotatingFile { path: path.to_path_buf(), file: Some(file), size, policy })
+    }
+
+    pub(crate) fn write_line(&mut self, line: &[u8]) -> io::Result<()> {
+        if self.policy.max_bytes > 0 && self.size > 0 && self.size + line.len() as u64 > self.policy.max_bytes {
+            self.rotate(self.policy.keep)?;
+        }
+        let file = match self.file.as_mut() {
+            Some(file) => file,
+            None => return Err(io::Error::other("log file is closed")),
+        };
+        file.write_all(line)?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+
+    fn rotate(&mut self, keep: usize) -> io::Result<()> {
+        self.file = None;
+        let rotated = rotate::rotate_by_rename(&self.path, keep);
+        // Keep logging even if the rename failed (e.g. a viewer holds the file)
+        *self = RotatingFile::open(&self.path, self.policy)?;
+        rotated
+    }
+
+    fn truncate(&mut self) -> io::Result<()> {
+        if let Some(file) = &self.file {
+            file.set_len(0)?;
+        }
+        self.size = 0;
+        Ok(())
+    }
+}
+
+fn writer(path: &Path) -> Option<Arc<Mutex<RotatingFile>>> {
+    WRITERS.lock().ok()?.get(path).and_then(Weak::upgrade)
+}
+
+pub(crate) fn is_capturing(path: &Path) -> bool {
+    writer(path).is_some()
+}
+
+fn with_writer(path: &Path, action: impl FnOnce(&mut RotatingFile) -> io::Result<()>) -> io::Result<()> {
+    let writer = writer(path).ok_or_else(|| io::Error::other("not capturing"))?;
+    let mut writer = writer.lock().map_err(|e| io::Error::other(e.to_string()))?;
+    action(&mut writer)
+}
+
+pub(crate) fn rotate(path: &Path, keep: usize) -> io::Result<()> {
+    with_writer(path, |writer| writer.rotate(keep))
+}
+
+pub(crate) fn truncate(path: &Path) -> io::Result<()> {
+    with_writer(path, RotatingFile::truncate)
+}
+
+fn copy_lines(reader: OutputStream, writer: Arc<Mutex<RotatingFile>>) {
+    tokio::spawn(async move {
+        let mut reader = BufReader::new(reader);
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            match reader.read_until(b'\n', &mut line).await {
+                // The pipe closes when the process exits
+                Ok(0) | Err(_) => break,
+                Ok(_) => {
+                    if !line.ends_with(b"\n") {
+                        line.push(b'\n');
+                    }
+                    if let Ok(mut writer) = writer.lock() {
+                        let _ = writer.write_line(&line);
+                    }
+                }
+            }
+        }
+    });
+}
+
+// Send the child's stdout and stderr to `path`. Must be called from the async
+// runtime.
+pub(crate) fn capture(child: &mut dyn Process, path: &Path, policy: Policy) -> Result<(), String> {
+    let writer = RotatingFile::open(path, policy).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let writer = Arc::new(Mutex::new(writer));
+    if let Ok(mut writers) = WRITERS.lock() {
+        writers.retain(|_, writer| writer.strong_count() > 0);
+        writers.insert(path.to_path_buf(), Arc::downgrade(&writer));
+    }
+
+    for stream in child.take_output() {
+        copy_lines(stream, writer.clone());
+    }
+    Ok(())
+}
@@ -0,0 +1,305 @@
+// Service logs. Everything a server prints is captured into logs/<service>.log
+// (see capture), next to the logs the servers write themselves: Apache's
+// error.log and access.log, MySQL's error log when my.cnf names one, and the
+// error_log of the active PHP. get_service_logs pages through any of them by
+// byte offset; subscribe_logs (see tail) follows them as they grow, and
+// query_logs (see parse) searches them as typed records. rotate keeps their
+// size in check.
+
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use crate::php::{ini, switch};
+use crate::services::{find_service, registry, Service};
+use crate::get_project_root;
+
+pub(crate) mod capture;
+pub(crate) mod parse;
+pub(crate) mod rotate;
+pub(crate) mod tail;
+
+// Lines returned when the caller gives no limit, and the most it may ask for
+const DEFAULT_LINES: usize = 200;
+const MAX_LINES: usize = 5000;
+
+// Stop a page early rather than return one enormous line
+const MAX_PAGE_BYTES: u64 = 4 * 1024 * 1024;
+
+// Backward reads scan the file in blocks of this size
+const TAIL_BLOCK: u64 = 64 * 1024;
+
+// Severity, lowest first, so filters can ask for "warning and above"
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Debug,
+    Info,
+    Notice,
+    Warning,
+    Error,
+    Critical,
+}
+
+#[derive(Clone, Serialize)]
+pub struct LogFile {
+    // "console", "error", "access"
+    pub name: String,
+    pub path: String,
+    pub exists: bool,
+    pub size: u64,
+}
+
+#[derive(Serialize)]
+pub struct LogPage {
+    pub service: String,
+    pub log: String,
+    pub path: String,
+    pub lines: Vec<String>,
+    // Byte range of `lines` in the file; pass `end` as the next offset to read
+    // on, or `start` as `before` to page back
+    pub start: u64,
+    pub end: u64,
+    // File size when it was read
+    pub size: u64,
+}
+
+pub(crate) fn logs_dir(base_path: &Path) -> PathBuf {
+    base_path.join("logs")
+}
+
+// Where a service's captured stdout/stderr goes
+pub(crate) fn console_log(base_path: &Path, service: &str) -> PathBuf {
+    logs_dir(base_path).join(format!("{}.log", service))
+}
+
+// Where PHP logs when php.ini has no error_log of its own
+pub(crate) fn php_error_log_default(base_path: &Path) -> PathBuf {
+    logs_dir(base_path).join("php_error.log")
+}
+
+// error_log of the active PHP. None when PHP logs to syslog or, without
+// error_log, into Apache's error log. Relative paths are relative to Apache's
+// working directory, the base path.
+pub(crate) fn php_error_log(base_path: &Path) -> Option<PathBuf> {
+    let content = std::fs::read_to_string(ini::php_ini_path(&switch::current_dir(base_path))).ok()?;
+    let value = ini::get_directive(&content, "error_log")?;
+    if value.is_empty() || value == "syslog" {
+        return None;
+    }
+    Some(base_path.join(value))
+}
+
+// The service's logs by name. The console capture is listed unless the
+// service already reports it under another name (MySQL's error log when
+// my.cnf does not set one).
+pub(crate) fn log_sources(service: &dyn Service, base_path: &Path) -> Vec<(String, PathBuf)> {
+    let mut sources: Vec<(String, PathBuf)> = service
+        .log_files(base_path)
+        .into_iter()
+        .map(|(name, path)| (name.to_string(), path))
+        .collect();
+    let console = console_log(base_path, service.name());
+    if !sources.iter().any(|(_, path)| *path == console) {
+        sources.push(("console".to_string(), console));
+    }
+    sources
+}
+
+// Logs of a registered service, or of "php", which is not one
+pub(crate) fn sources_of(base_path: &Path, service: &str) -> Result<Vec<(String, PathBuf)>, String> {
+    if service == "php" {
+        return Ok(php_error_log(base_path)
+            .map(|path| vec![("error".to_string(), path)])
+            .unwrap_or_default());
+    }
+    Ok(log_sources(find_service(service)?, base_path))
+}
+
+// Names that sources_of accepts
+pub(crate) fn log_services() -> Vec<&'static str> {
+    let mut services: Vec<&'static str> = registry().iter().map(|service| service.name()).collect();
+    services.push("php");
+    services
+}
+
+fn find_source(base_path: &Path, service: &str, log: Option<&str>) -> Result<(String, PathBuf), String> {
+    let sources = sources_of(base_path, service)?;
+    let found = match log {
+        // The error log is what people look for first
+        None => sources.iter().find(|(name, _)| name == "error").or(sources.first()),
+        Some(log) => sources.iter().find(|(name, _)| name == log),
+    };
+    found.cloned().ok_or_else(|| match log {
+        Some(log) => format!("{} has no '{}' log", service, log),
+        None => format!("{} does not log to a file", service),
+    })
+}
+
+// The last lines of a service's error log, for crash reports
+pub(crate) fn last_lines(base_path: &Path, service: &str, limit: usize) -> Vec<String> {
+    let Ok((_, path)) = find_source(base_path, service, None) else { return Vec::new() };
+    last_lines_of(&path, limit)
+}
+
+pub(crate) fn last_lines_of(path: &Path, limit: usize) -> Vec<String> {
+    let Ok(mut file) = File::open(path) else { return Vec::new() };
+    let size = file.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+    read_backward(&mut file, size, limit).map(|(lines, _)| lines).unwrap_or_default()
+}
+
+// Severity of a raw log line, from the markers each server uses:
+// "[php:error]"/"[core:warn]" (Apache), "[ERROR]"/"[Warning]"/"[Note]" (MySQL),
+// "PHP Fatal error:"/"PHP Warning:" (PHP)
+pub(crate) fn line_level(line: &str) -> Option<Level> {
+    const MARKERS: &[(&str, Level)] = &[
+        (":emerg]", Level::Critical),
+        (":alert]", Level::Critical),
+        (":crit]", Level::Critical),
+        (":error]", Level::Error),
+        (":warn]", Level::Warning),
+        (":notice]", Level::Notice),
+        (":info]", Level::Info),
+        (":debug]", Level::Debug),
+        (":trace", Level::Debug),
+        ("[ERROR]", Level::Error),
+        ("[Warning]", Level::Warning),
+        ("[Note]", Level::Notice),
+        ("[System]", Level::Info),
+        ("PHP Fatal error:", Level::Error),
+        ("PHP Parse error:", Level::Error),
+        ("PHP Warning:", Level::Warning),
+        ("PHP Notice:", Level::Notice),
+        ("PHP Deprecated:", Level::Notice),
+    ];
+    MARKERS
+        .iter()
+        .find(|(marker, _)| line.contains(marker))
+        .map(|(_, level)| *level)
+}
+
+fn to_lines(bytes: &[u8]) -> Vec<String> {
+    bytes
+        .split_inclusive(|byte| *byte == b'\n')
+        .map(|line| {
+            let line = line.strip_suffix(b"\n").unwrap_or(line);
+            let line = line.strip_suffix(b"\r").unwrap_or(line);
+            String::from_utf8_lossy(line).into_owned()
+        })
+        .collect()
+}
+
+// Up to `limit` lines from `offset` on; the returned end is where they stop
+pub(crate) fn read_forward(file: &mut File, offset: u64, limit: usize) -> io::Result<(Vec<String>, u64)> {
+    file.seek(SeekFrom::Start(offset))?;
+    let mut reader = BufReader::new(file);
+    let mut bytes = Vec::new();
+    let mut count = 0;
+    while count < limit && (bytes.len() as u64) < MAX_PAGE_BYTES {
+        if reader.read_until(b'\n', &mut bytes)? == 0 {
+            break;
+        }
+        count += 1;
+    }
+    let end = offset + bytes.len() as u64;
+    Ok((to_lines(&bytes), end))
+}
+
+// Up to `limit` whole lines ending at `before`; the returned start is where they begin
+pub(crate) fn read_backward(file: &mut (impl Read + Seek), before: u64, limit: usize) -> io::Result<(Vec<String>, u64)> {
+    let mut start = before;
+    let mut newlines = 0;
+    let mut block = vec![0; TAIL_BLOCK as usize];
+    'scan: while start > 0 && before - start < MAX_PAGE_BYTES {
+        let length = TAIL_BLOCK.min(start);
+        let block_start = start - length;
+        file.seek(SeekFrom::Start(block_start))?;
+        file.read_exact(&mut block[..length as usize])?;
+        for index in (0..length as usize).rev() {
+            let position = block_start + index as u64;
+            // The newline ending the last line does not start a new one
+            if block[index] == b'\n' && position + 1 != before {
+                newlines += 1;
+                if newlines == limit {
+                    start = position + 1;
+                    break 'scan;
+                }
+            }
+        }
+        start = block_start;
+    }
+
+    let mut bytes = vec![0; (before - start) as usize];
+    file.seek(SeekFrom::Start(start))?;
+    file.read_exact(&mut bytes)?;
+    Ok((to_lines(&bytes), start))
+}
+
+// The logs a service has, with their sizes
+#[tauri::command]
+pub async fn list_service_logs(service: String) -> Result<Vec<LogFile>, String> {
+    let base_path = get_project_root()?;
+    Ok(sources_of(&base_path, &service)?
+        .into_iter()
+        .map(|(name, path)| {
+            let metadata = std::fs::metadata(&path).ok();
+            LogFile {
+                name,
+                path: path.display().to_string(),
+                exists: metadata.is_some(),
+                size: metadata.map(|metadata| metadata.len()).unwrap_or(0),
+            }
+        })
+        .collect())
+}
+
+// A page of one of the service's logs (the error log when `log` is None).
+// With `offset` it reads forward from there; otherwise it returns the lines
+// just before `before`, or the end of the file.
+#[tauri::command]
+pub async fn get_service_logs(
+    service: String,
+    log: Option<String>,
+    offset: Option<u64>,
+    before: Option<u64>,
+    limit: Option<usize>,
+) -> Result<LogPage, String> {
+    let base_path = get_project_root()?;
+    let (log, path) = find_source(&base_path, &service, log.as_deref())?;
+    let limit = limit.unwrap_or(DEFAULT_LINES).clamp(1, MAX_LINES);
+
+    let mut page = LogPage {
+        service,
+        log,
+        path: path.display().to_string(),
+        lines: Vec::new(),
+        start: 0,
+        end: 0,
+        size: 0,
+    };
+    let mut file = match File::open(&path) {
+        Ok(file) => file,
+        // Nothing logged yet
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(page),
+        Err(e) => return Err(format!("Failed to open {}: {}", path.display(), e)),
+    };
+    page.size = file.metadata().map_err(|e| e.to_string())?.len();
+
+    let read = match offset {
+        // An offset past the end means the file was rotated or cleared
+        Some(offset) => {
+            let start = if offset > page.size { 0 } else { offset };
+            read_forward(&mut file, start, limit).map(|(lines, end)| (lines, start, end))
+        }
+        None => {
+            let end = before.unwrap_or(page.size).min(page.size);
+            read_backward(&mut file, end, limit).map(|(lines, start)| (lines, start, end))
+        }
+    };
+    let (lines, start, end) = read.map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    page.lines = lines;
+    page.start = start;
+    page.end = end;
+    Ok(page)
+}
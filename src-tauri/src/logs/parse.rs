@@ -0,0 +1,604 @@
+// Typed log records. Every log we know has a fixed format: Apache's access log
+// (common or combined), Apache's error log, MySQL 8's error log and PHP's
+// error_log. Lines that do not start a record (PHP stack traces, multi-line
+// MySQL messages) are continuations and join the record before them.
+
+use chrono::{DateTime, FixedOffset, Local, NaiveDateTime, TimeZone, Utc};
+use flate2::read::GzDecoder;
+use serde::Serialize;
+use std::fs::File;
+use std::io::{self, Cursor, Read, Seek};
+use std::path::Path;
+
+use super::rotate::generations;
+use super::{find_source, line_level, read_backward, Level};
+use crate::get_project_root;
+
+// Lines read per step when scanning a log from the end
+const PAGE_LINES: usize = 1000;
+
+// Give up on a query after reading this much, so a huge log cannot hang the UI
+const MAX_SCAN_BYTES: u64 = 256 * 1024 * 1024;
+
+const DEFAULT_RECORDS: usize = 500;
+const MAX_RECORDS: usize = 10_000;
+
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum LogFormat {
+    ApacheAccess,
+    ApacheError,
+    MySql,
+    Php,
+    // Console output and anything else: one record per line
+    Plain,
+}
+
+#[derive(Clone, Serialize)]
+pub struct LogRecord {
+    // RFC 3339, None when the line carries no time
+    pub timestamp: Option<String>,
+    pub level: Option<Level>,
+    // Apache module, MySQL subsystem, "php", or the client address for access logs
+    pub source: Option<String>,
+    pub message: String,
+    // Request path for access logs, "file:line" for PHP errors
+    pub path: Option<String>,
+    pub status: Option<u16>,
+    pub bytes: Option<u64>,
+    #[serde(skip)]
+    time: Option<DateTime<FixedOffset>>,
+}
+
+#[derive(Serialize)]
+pub struct LogRecords {
+    pub service: String,
+    pub log: String,
+    pub path: String,
+    // Oldest first
+    pub records: Vec<LogRecord>,
+    // The limit or the scan budget was reached before the start of the range
+    pub more: bool,
+}
+
+impl LogRecord {
+    fn new(time: Option<DateTime<FixedOffset>>, level: Option<Level>, message: &str) -> LogRecord {
+        LogRecord {
+            timestamp: time.map(|time| time.to_rfc3339()),
+            level,
+            source: None,
+            message: message.trim().to_string(),
+            path: None,
+            status: None,
+            bytes: None,
+            time,
+        }
+    }
+}
+
+impl LogFormat {
+    pub(crate) fn of(service: &str, log: &str) -> LogFormat {
+        match (service, log) {
+            ("apache", "access") => LogFormat::ApacheAccess,
+            ("apache", "error") => LogFormat::ApacheError,
+            // Without log-error, mysqld's console is its error log
+            ("mysql", "error") | ("mysql", "console") => LogFormat::MySql,
+            ("php", _) => LogFormat::Php,
+            _ => LogFormat::Plain,
+        }
+    }
+
+    // The record `line` starts, None for a continuation line
+    pub(crate) fn parse(self, line: &str) -> Option<LogRecord> {
+        match self {
+            // One request per line, there is nothing to continue
+            LogFormat::ApacheAccess => parse_access(line).or_else(|| Some(LogRecord::new(None, None, line))),
+            LogFormat::ApacheError => parse_apache_error(line),
+            LogFormat::MySql => parse_mysql(line),
+            LogFormat::Php => parse_php(line),
+            LogFormat::Plain => Some(LogRecord::new(None, line_level(line), line)),
+        }
+    }
+}
+
+fn local_time(naive: NaiveDateTime) -> Option<DateTime<FixedOffset>> {
+    Local.from_local_datetime(&naive).earliest().map(|time| time.fixed_offset())
+}
+
+// "[inside] rest" -> ("inside", "rest")
+fn bracketed(text: &str) -> Option<(&str, &str)> {
+    let rest = text.trim_start().strip_prefix('[')?;
+    let end = rest.find(']')?;
+    Some((&rest[..end], &rest[end + 1..]))
+}
+
+// 127.0.0.1 - frank [10/Oct/2000:13:55:36 -0700] "GET /a.gif HTTP/1.0" 200 2326 "referer" "agent"
+fn parse_access(line: &str) -> Option<LogRecord> {
+    let (client, rest) = line.split_once(' ')?;
+    let (time, rest) = bracketed(&rest[rest.find('[')?..])?;
+    let time = DateTime::parse_from_str(time, "%d/%b/%Y:%H:%M:%S %z").ok()?;
+
+    let rest = rest.trim_start().strip_prefix('"')?;
+    let end = rest.find("\" ")?;
+    let request = &rest[..end];
+    let mut fields = rest[end + 2..].split_whitespace();
+    let status: u16 = fields.next()?.parse().ok()?;
+    // "-" when nothing was sent
+    let bytes = fields.next().and_then(|bytes| bytes.parse().ok()).or(Some(0));
+
+    let level = match status {
+        500.. => Level::Error,
+        400..=499 => Level::Warning,
+        _ => Level::Info,
+    };
+    let mut record = LogRecord::new(Some(time), Some(level), request);
+    record.source = Some(client.to_string());
+    record.path = request.split_whitespace().nth(1).map(str::to_string);
+    record.status = Some(status);
+    record.bytes = bytes;
+    Some(record)
+}
+
+fn apache_level(level: &str) -> Option<Level> {
+    match level {
+        "emerg" | "alert" | "crit" => Some(Level::Critical),
+        "error" => Some(Level::Error),
+        "warn" => Some(Level::Warning),
+        "notice" => Some(Level::Notice),
+        "info" => Some(Level::Info),
+        level if level == "debug" || level.starts_with("trace") => Some(Level::Debug),
+        _ => None,
+    }
+}
+
+// [Fri Sep 09 10:42:29.902022 2011] [core:error] [pid 35708:tid 4328] [client 1.2.3.4:5] AH00128: File does not exist: /x
+// Apache 2.2 has no microseconds and no module: [Wed Oct 11 14:32:52 2000] [error] [client 1.2.3.4] ...
+fn parse_apache_error(line: &str) -> Option<LogRecord> {
+    let (time, rest) = bracketed(line)?;
+    let time = NaiveDateTime::parse_from_str(time, "%a %b %d %H:%M:%S%.f %Y").ok()?;
+    let (tag, mut rest) = bracketed(rest)?;
+    let (module, level) = match tag.split_once(':') {
+        Some((module, level)) => (Some(module), level),
+        None => (None, tag),
+    };
+
+    // Process and client fields come before the message
+    while let Some((field, after)) = bracketed(rest) {
+        if !(field.starts_with("pid ") || field.starts_with("client ") || field.starts_with("remote ")) {
+            break;
+        }
+        rest = after;
+    }
+
+    let mut record = LogRecord::new(local_time(time), apache_level(level), rest);
+    record.source = module.map(str::to_string);
+    // PHP errors reach the error log through mod_php
+    if module == Some("php") || record.message.starts_with("PHP ") {
+        record.path = php_location(&record.message);
+    }
+    Some(record)
+}
+
+// 2024-01-15T10:30:45.123456Z 0 [System] [MY-010116] [Server] /usr/sbin/mysqld (mysqld 8.0.35) starting as process 1
+fn parse_mysql(line: &str) -> Option<LogRecord> {
+    let (time, rest) = line.split_once(' ')?;
+    let time = DateTime::parse_from_rfc3339(time).ok()?;
+    // Thread id
+    let rest = rest.trim_start().trim_start_matches(|c: char| c.is_ascii_digit());
+
+    let (label, mut rest) = bracketed(rest)?;
+    let level = match label {
+        "ERROR" => Level::Error,
+        "Warning" => Level::Warning,
+        "Note" => Level::Notice,
+        "System" => Level::Info,
+        _ => return None,
+    };
+    let mut source = None;
+    // [MY-010116] error code, then [Server] / [InnoDB] / [Repl] subsystem
+    while let Some((field, after)) = bracketed(rest) {
+        if !field.starts_with("MY-") {
+            source = Some(field.to_string());
+        }
+        rest = after;
+        if source.is_some() {
+            break;
+        }
+    }
+
+    let mut record = LogRecord::new(Some(time), Some(level), rest);
+    record.source = source;
+    Some(record)
+}
+
+fn php_level(kind: &str) -> Option<Level> {
+    match kind {
+        "Fatal error" | "Parse error" | "Recoverable fatal error" | "Catchable fatal error" => Some(Level::Error),
+        "Warning" => Some(Level::Warning),
+        "Notice" | "Deprecated" | "Strict Standards" => Some(Level::Notice),
+        _ => None,
+    }
+}
+
+// "... in /var/www/app/index.php on line 12" -> "/var/www/app/index.php:12".
+// Uncaught exceptions name it on their last line, "  thrown in ... on line 12".
+fn php_location(message: &str) -> Option<String> {
+    message.lines().find_map(|text| {
+        let (before, line) = text.rsplit_once(" on line ")?;
+        let line: u32 = line.trim().trim_end_matches(|c: char| !c.is_ascii_digit()).parse().ok()?;
+        let (_, file) = before.rsplit_once(" in ")?;
+        Some(format!("{}:{}", file.trim(), line))
+    })
+}
+
+// [15-Jan-2024 10:30:45 UTC] PHP Warning:  Undefined variable $x in /var/www/a.php on line 3
+// error_log() calls write their message without the "PHP <kind>:" part
+fn parse_php(line: &str) -> Option<LogRecord> {
+    let (stamp, rest) = bracketed(line)?;
+    let (time, zone) = stamp.rsplit_once(' ')?;
+    let naive = NaiveDateTime::parse_from_str(time, "%d-%b-%Y %H:%M:%S").ok()?;
+    // PHP writes the date.timezone name; only UTC is known without a zone database
+    let time = match zone {
+        "UTC" => Some(Utc.from_utc_datetime(&naive).fixed_offset()),
+        _ => local_time(naive),
+    };
+
+    let rest = rest.trim_start();
+    let (level, message) = match rest.strip_prefix("PHP ").and_then(|rest| rest.split_once(':')) {
+        Some((kind, message)) if php_level(kind).is_some() => (php_level(kind), message),
+        _ => (Some(Level::Info), rest),
+    };
+
+    let mut record = LogRecord::new(time, level, message);
+    record.source = Some("php".to_string());
+    record.path = php_location(&record.message);
+    Some(record)
+}
+
+// Records of consecutive lines, continuations folded into the record before them
+pub(crate) fn parse_records(format: LogFormat, lines: &[String]) -> Vec<LogRecord> {
+    let mut records: Vec<LogRecord> = Vec::new();
+    for line in lines.iter().filter(|line| !line.trim().is_empty()) {
+        match (format.parse(line), records.last_mut()) {
+            (Some(record), _) => records.push(record),
+            (None, Some(last)) => {
+                last.message.push('\n');
+                last.message.push_str(line.trim_end());
+                if last.path.is_none() && last.source.as_deref() == Some("php") {
+                    last.path = php_location(line);
+                }
+            }
+            // Nothing to attach to at the start of the log
+            (None, None) => records.push(LogRecord::new(None, line_level(line), line)),
+        }
+    }
+    records
+}
+
+pub(crate) struct RecordFilter {
+    pub from: Option<DateTime<FixedOffset>>,
+    pub to: Option<DateTime<FixedOffset>>,
+    pub level: Option<Level>,
+}
+
+impl RecordFilter {
+    fn matches(&self, record: &LogRecord) -> bool {
+        if let Some(minimum) = self.level {
+            if record.level.is_none_or(|level| level < minimum) {
+                return false;
+            }
+        }
+        if self.from.is_none() && self.to.is_none() {
+            return true;
+        }
+        // A time range only keeps records that have a time
+        record.time.is_some_and(|time| {
+            self.from.is_none_or(|from| time >= from) && self.to.is_none_or(|to| time <= to)
+        })
+    }
+}
+
+trait ReadSeek: Read + Seek {}
+
+impl<T: Read + Seek> ReadSeek for T {}
+
+// A log generation to scan from the end, with its length. Compressed copies
+// are unpacked in memory; they are at most one rotation size.
+fn open_generation(path: &Path) -> io::Result<(Box<dyn ReadSeek>, u64)> {
+    let file = File::open(path)?;
+    if path.extension().is_some_and(|extension| extension == "gz") {
+        let mut bytes = Vec::new();
+        GzDecoder::new(file).read_to_end(&mut bytes)?;
+        let length = bytes.len() as u64;
+        return Ok((Box::new(Cursor::new(bytes)), length));
+    }
+    let length = file.metadata()?.len();
+    Ok((Box::new(file), length))
+}
+
+// The newest `limit` records passing `filter`, read from the end of the log
+// back into its rotated (and compressed) copies until the start of the time range. Returns them
+// oldest first, and whether the scan stopped before the range was exhausted.
+pub(crate) fn query(path: &Path, format: LogFormat, filter: &RecordFilter, limit: usize) -> io::Result<(Vec<LogRecord>, bool)> {
+    let mut found = Vec::new();
+    let mut scanned = 0;
+
+    for generation in generations(path) {
+        let (mut file, mut before) = match open_generation(&generation) {
+            Ok(opened) => opened,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        let mut carried: Vec<String> = Vec::new();
+
+        while before > 0 {
+            if scanned > MAX_SCAN_BYTES {
+                found.reverse();
+                return Ok((found, true));
+            }
+            let (mut lines, start) = read_backward(&mut file, before, PAGE_LINES)?;
+            scanned += before - start;
+            before = start;
+            lines.append(&mut carried);
+            // Leading continuation lines belong to a record on the previous page
+            if start > 0 {
+                let first = lines.iter().position(|line| format.parse(line).is_some()).unwrap_or(lines.len());
+                carried = lines.drain(..first).collect();
+            }
+
+            let mut before_range = false;
+            for record in parse_records(format, &lines).into_iter().rev() {
+                if let (Some(from), Some(time)) = (filter.from, record.time) {
+                    before_range |= time < from;
+                }
+                if filter.matches(&record) {
+                    found.push(record);
+                    if found.len() == limit {
+                        found.reverse();
+                        return Ok((found, true));
+                    }
+                }
+            }
+            // Finish the page anyway: access log entries are written in
+            // completion order, with the time the request started
+            if before_range {
+                found.reverse();
+                return Ok((found, false));
+            }
+        }
+    }
+    found.reverse();
+    Ok((found, false))
+}
+
+fn parse_time(value: Option<&str>) -> Result<Option<DateTime<FixedOffset>>, String> {
+    value
+        .filter(|value| !value.is_empty())
+        .map(|value| {
+            DateTime::parse_from_rfc3339(value)
+                .map_err(|_| format!("Invalid time '{}', expected RFC 3339 like 2024-01-15T10:30:00+01:00", value))
+        })
+        .transpose()
+}
+
+// Records of one of the service's logs (the error log when `log` is None),
+// limited to a time range and a lowest severity. Newest records win when
+// there are more than `limit`.
+#[tauri::command]
+pub async fn query_logs(
+    service: String,
+    log: Option<String>,
+    from: Option<String>,
+    to: Option<String>,
+    level: Option<Level>,
+    limit: Option<usize>,
+) -> Result<LogRecords, String> {
+    let base_path = get_project_root()?;
+    let (log, path) = find_source(&base_path, &service, log.as_deref())?;
+    let filter = RecordFilter {
+        from: parse_time(from.as_deref())?,
+        to: parse_time(to.as_deref())?,
+        level,
+    };
+    let limit = limit.unwrap_or(DEFAULT_RECORDS).clamp(1, MAX_RECORDS);
+
+    let format = LogFormat::of(&service, &log);
+    let (records, more) = query(&path, format, &filter, limit).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    Ok(LogRecords {
+        service,
+        log,
+        path: path.display().to_string(),
+        records,
+        more,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Level, source, message and path of a record
+    type Fields<'a> = (Option<Level>, Option<&'a str>, &'a str, Option<&'a str>);
+
+    fn fields(record: &LogRecord) -> Fields<'_> {
+        (record.level, record.source.as_deref(), record.message.as_str(), record.path.as_deref())
+    }
+
+    // Wall-clock time as written in the log, whatever the local zone
+    fn written_at(record: &LogRecord) -> Option<String> {
+        record.time.map(|time| time.naive_local().format("%Y-%m-%d %H:%M:%S%.f").to_string())
+    }
+
+    #[test]
+    fn access_lines() {
+        let cases: &[(&str, Fields, &str, u16, u64)] = &[
+            (
+                "127.0.0.1 - frank [10/Oct/2000:13:55:36 -0700] \"GET /apache_pb.gif HTTP/1.0\" 200 2326",
+                (Some(Level::Info), Some("127.0.0.1"), "GET /apache_pb.gif HTTP/1.0", Some("/apache_pb.gif")),
+                "2000-10-10T13:55:36-07:00",
+                200,
+                2326,
+            ),
+            (
+                "::1 - - [15/Jan/2024:10:30:45 +0100] \"GET /missing.php?a=1 HTTP/1.1\" 404 196 \"http://localhost/\" \"Mozilla/5.0 (X11; Linux x86_64)\"",
+                (Some(Level::Warning), Some("::1"), "GET /missing.php?a=1 HTTP/1.1", Some("/missing.php?a=1")),
+                "2024-01-15T10:30:45+01:00",
+                404,
+                196,
+            ),
+            (
+                "192.168.1.5 - - [15/Jan/2024:10:30:46 +0000] \"HEAD / HTTP/1.1\" 304 -",
+                (Some(Level::Info), Some("192.168.1.5"), "HEAD / HTTP/1.1", Some("/")),
+                "2024-01-15T10:30:46+00:00",
+                304,
+                0,
+            ),
+            (
+                "127.0.0.1 - - [15/Jan/2024:10:30:47 +0000] \"POST /api HTTP/1.1\" 502 - \"-\" \"curl/8.5.0\"",
+                (Some(Level::Error), Some("127.0.0.1"), "POST /api HTTP/1.1", Some("/api")),
+                "2024-01-15T10:30:47+00:00",
+                502,
+                0,
+            ),
+        ];
+        for (line, expected, timestamp, status, bytes) in cases {
+            let record = parse_access(line).unwrap_or_else(|| panic!("not parsed: {}", line));
+            assert_eq!(fields(&record), *expected, "{}", line);
+            assert_eq!(record.timestamp.as_deref(), Some(*timestamp), "{}", line);
+            assert_eq!(record.status, Some(*status), "{}", line);
+            assert_eq!(record.bytes, Some(*bytes), "{}", line);
+        }
+
+        assert!(parse_access("AH00558: httpd: Could not reliably determine the server's fully qualified domain name").is_none());
+        // The access format still shows lines it cannot parse
+        let record = LogFormat::ApacheAccess.parse("garbage").unwrap();
+        assert_eq!(fields(&record), (None, None, "garbage", None));
+    }
+
+    #[test]
+    fn apache_error_lines() {
+        let cases: &[(&str, Fields, &str)] = &[
+            // 2.4
+            (
+                "[Fri Sep 09 10:42:29.902022 2011] [core:error] [pid 35708:tid 4328] [client 72.15.99.187:5] AH00128: File does not exist: /usr/local/apache2/htdocs/favicon.ico",
+                (Some(Level::Error), Some("core"), "AH00128: File does not exist: /usr/local/apache2/htdocs/favicon.ico", None),
+                "2011-09-09 10:42:29.902022",
+            ),
+            (
+                "[Mon Jan 15 10:30:45.123456 2024] [mpm_event:notice] [pid 1:tid 2] AH00489: Apache/2.4.58 (Unix) configured -- resuming normal operations",
+                (Some(Level::Notice), Some("mpm_event"), "AH00489: Apache/2.4.58 (Unix) configured -- resuming normal operations", None),
+                "2024-01-15 10:30:45.123456",
+            ),
+            (
+                "[Mon Jan 15 10:30:45.123456 2024] [php:warn] [pid 12] [client ::1:51234] PHP Warning:  Undefined variable $x in C:/www/app/index.php on line 3",
+                (Some(Level::Warning), Some("php"), "PHP Warning:  Undefined variable $x in C:/www/app/index.php on line 3", Some("C:/www/app/index.php:3")),
+                "2024-01-15 10:30:45.123456",
+            ),
+            // 2.2: no microseconds, no module
+            (
+                "[Wed Oct 11 14:32:52 2000] [error] [client 127.0.0.1] client denied by server configuration: /export/home/live/ap/htdocs/test",
+                (Some(Level::Error), None, "client denied by server configuration: /export/home/live/ap/htdocs/test", None),
+                "2000-10-11 14:32:52",
+            ),
+            (
+                "[Wed Oct 11 14:32:53 2000] [crit] (2)No such file or directory: could not open mime types config file",
+                (Some(Level::Critical), None, "(2)No such file or directory: could not open mime types config file", None),
+                "2000-10-11 14:32:53",
+            ),
+        ];
+        for (line, expected, written) in cases {
+            let record = parse_apache_error(line).unwrap_or_else(|| panic!("not parsed: {}", line));
+            assert_eq!(fields(&record), *expected, "{}", line);
+            assert_eq!(written_at(&record).as_deref(), Some(*written), "{}", line);
+        }
+
+        assert!(parse_apache_error("AH00558: httpd: Could not reliably determine the server's fully qualified domain name").is_none());
+    }
+
+    #[test]
+    fn mysql_lines() {
+        let cases: &[(&str, Fields, &str)] = &[
+            (
+                "2024-01-15T10:30:45.123456Z 0 [System] [MY-010116] [Server] /usr/sbin/mysqld (mysqld 8.0.35) starting as process 1",
+                (Some(Level::Info), Some("Server"), "/usr/sbin/mysqld (mysqld 8.0.35) starting as process 1", None),
+                "2024-01-15T10:30:45.123456+00:00",
+            ),
+            (
+                "2024-01-15T10:30:46.000001Z 1 [ERROR] [MY-012574] [InnoDB] Unable to lock ./ibdata1 error: 11",
+                (Some(Level::Error), Some("InnoDB"), "Unable to lock ./ibdata1 error: 11", None),
+                "2024-01-15T10:30:46.000001+00:00",
+            ),
+            // Without a subsystem
+            (
+                "2024-01-15T10:30:47.5+01:00 0 [Warning] [MY-010068] CA certificate ca.pem is self signed.",
+                (Some(Level::Warning), None, "CA certificate ca.pem is self signed.", None),
+                "2024-01-15T10:30:47.500+01:00",
+            ),
+            (
+                "2024-01-15T10:30:48.000000Z 7 [Note] [MY-010914] Aborted connection 7 to db: 'app' user: 'root' host: 'localhost' (Got an error reading communication packets).",
+                (Some(Level::Notice), None, "Aborted connection 7 to db: 'app' user: 'root' host: 'localhost' (Got an error reading communication packets).", None),
+                "2024-01-15T10:30:48+00:00",
+            ),
+        ];
+        for (line, expected, timestamp) in cases {
+            let record = parse_mysql(line).unwrap_or_else(|| panic!("not parsed: {}", line));
+            assert_eq!(fields(&record), *expected, "{}", line);
+            assert_eq!(record.timestamp.as_deref(), Some(*timestamp), "{}", line);
+        }
+
+        // Continuations of a multi-line message
+        assert!(parse_mysql("Please check the data directory permissions.").is_none());
+        assert!(parse_mysql("2024-01-15T10:30:45Z 0 [Trace] unknown label").is_none());
+    }
+
+    #[test]
+    fn php_lines() {
+        let record = parse_php("[15-Jan-2024 10:30:45 UTC] PHP Warning:  Undefined variable $x in /var/www/a.php on line 3").unwrap();
+        assert_eq!(
+            fields(&record),
+            (Some(Level::Warning), Some("php"), "Undefined variable $x in /var/www/a.php on line 3", Some("/var/www/a.php:3"))
+        );
+        assert_eq!(record.timestamp.as_deref(), Some("2024-01-15T10:30:45+00:00"));
+
+        let record = parse_php("[15-Jan-2024 10:30:45 Europe/Berlin] PHP Deprecated:  strlen(): Passing null in /var/www/b.php on line 9").unwrap();
+        assert_eq!(
+            fields(&record),
+            (Some(Level::Notice), Some("php"), "strlen(): Passing null in /var/www/b.php on line 9", Some("/var/www/b.php:9"))
+        );
+        assert_eq!(written_at(&record).as_deref(), Some("2024-01-15 10:30:45"));
+
+        // error_log() output has no "PHP <kind>:" part
+        let record = parse_php("[15-Jan-2024 10:30:46 UTC] cache warmed in 12ms").unwrap();
+        assert_eq!(fields(&record), (Some(Level::Info), Some("php"), "cache warmed in 12ms", None));
+
+        assert!(parse_php("Stack trace:").is_none());
+        assert!(parse_php("#0 {main}").is_none());
+    }
+
+    #[test]
+    fn stack_traces_join_their_record() {
+        let lines: Vec<String> = [
+            "[15-Jan-2024 10:30:45 UTC] PHP Fatal error:  Uncaught Exception: boom in /var/www/a.php:5",
+            "Stack trace:",
+            "#0 /var/www/index.php(3): run()",
+            "#1 {main}",
+            "  thrown in /var/www/a.php on line 5",
+            "[15-Jan-2024 10:30:46 UTC] PHP Notice:  Undefined index: id in /var/www/b.php on line 7",
+        ]
+        .iter()
+        .map(|line| line.to_string())
+        .collect();
+
+        let records = parse_records(LogFormat::Php, &lines);
+        assert_eq!(records.len(), 2);
+        assert_eq!(
+            fields(&records[0]),
+            (
+                Some(Level::Error),
+                Some("php"),
+                "Uncaught Exception: boom in /var/www/a.php:5\nStack trace:\n#0 /var/www/index.php(3): run()\n#1 {main}\n  thrown in /var/www/a.php on line 5",
+                Some("/var/www/a.php:5")
+            )
+        );
+        assert_eq!(fields(&records[1]).3, Some("/var/www/b.php:7"));
+    }
+}
@@ -0,0 +1,296 @@
+// Log rotation and retention. A background sweep rotates every managed log
+// once it is too large or too old, following the [logs] settings: rotated
+// copies are numbered (error.log.1 is the newest), optionally gzipped, and
+// only the configured number is kept.
+//
+// How a log is rotated depends on who writes it. Captured console output is
+// rotated by its writer. A server's own logs are renamed and the server is
+// signalled to reopen them (Apache's graceful restart, mysqld's SIGHUP); where
+// signals are not available they are copied and truncated in place, which the
+// servers' append-mode writes survive. PHP opens error_log for every message,
+// so renaming it is enough.
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use super::{capture, console_log, log_sources, php_error_log, sources_of};
+use crate::services::{find_service, registry, tracked_pid, Service};
+use crate::settings::{self, LogSettings};
+use crate::{get_project_root, platform};
+
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+// Rotated copies are compressed once nothing has written to them for this long
+const COMPRESS_AFTER: Duration = Duration::from_secs(60);
+
+#[derive(Clone, Copy)]
+pub(crate) struct Policy {
+    // 0 when size does not matter
+    pub max_bytes: u64,
+    pub max_age: Option<Duration>,
+    pub keep: usize,
+    pub compress: bool,
+}
+
+impl Policy {
+    pub(crate) fn from_settings(logs: &LogSettings) -> Policy {
+        Policy {
+            max_bytes: logs.max_size_mb * 1024 * 1024,
+            max_age: (logs.max_age_days > 0).then(|| Duration::from_secs(logs.max_age_days * 24 * 60 * 60)),
+            keep: logs.keep.max(1) as usize,
+            compress: logs.compress,
+        }
+    }
+
+    pub(crate) fn load(base_path: &Path) -> Policy {
+        let settings = settings::load(base_path).unwrap_or_default();
+        Policy::from_settings(&settings.logs)
+    }
+}
+
+// path.<n>, the n-th newest rotated copy
+pub(crate) fn rotated_path(path: &Path, n: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(format!(".{}", n));
+    PathBuf::from(name)
+}
+
+fn gz_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(".gz");
+    PathBuf::from(name)
+}
+
+// The log and its rotated copies, newest first; compressed copies end in .gz
+pub(crate) fn generations(path: &Path) -> Vec<PathBuf> {
+    let mut generations = vec![path.to_path_buf()];
+    for n in 1.. {
+        let plain = rotated_path(path, n);
+        let gz = gz_path(&plain);
+        if plain.exists() {
+            generations.push(plain);
+        } else if gz.exists() {
+            generations.push(gz);
+        } else {
+            break;
+        }
+    }
+    generations
+}
+
+// Make room for a new path.1: drop the copies that would exceed `keep` (also
+// those left over from a larger setting) and move the rest up by one
+fn shift(path: &Path, keep: usize) -> io::Result<()> {
+    let mut n = keep;
+    loop {
+        let plain = rotated_path(path, n);
+        let gz = gz_path(&plain);
+        if n > keep && !plain.exists() && !gz.exists() {
+            break;
+        }
+        let _ = std::fs::remove_file(&plain);
+        let _ = std::fs::remove_file(&gz);
+        n += 1;
+    }
+
+    for n in (1..keep).rev() {
+        let from = rotated_path(path, n);
+        let to = rotated_path(path, n + 1);
+        if from.exists() {
+            std::fs::rename(&from, &to)?;
+        }
+        if gz_path(&from).exists() {
+            std::fs::rename(gz_path(&from), gz_path(&to))?;
+        }
+    }
+    Ok(())
+}
+
+// path -> path.1, for files nobody holds open or whose writer reopens them
+pub(crate) fn rotate_by_rename(path: &Path, keep: usize) -> io::Result<()> {
+    shift(path, keep)?;
+    std::fs::rename(path, rotated_path(path, 1))
+}
+
+// path is copied to path.1 and emptied, for files a running server keeps open
+fn rotate_by_copy(path: &Path, keep: usize) -> io::Result<()> {
+    shift(path, keep)?;
+    std::fs::copy(path, rotated_path(path, 1))?;
+    OpenOptions::new().write(true).open(path)?.set_len(0)
+}
+
+fn compress(path: &Path) -> io::Result<()> {
+    let target = gz_path(path);
+    let result = (|| {
+        let mut encoder = GzEncoder::new(File::create(&target)?, Compression::default());
+        io::copy(&mut File::open(path)?, &mut encoder)?;
+        encoder.finish()?.sync_all()
+    })();
+    match result {
+        Ok(()) => std::fs::remove_file(path),
+        Err(e) => {
+            let _ = std::fs::remove_file(&target);
+            Err(e)
+        }
+    }
+}
+
+// gzip the rotated copies writers are done with. A server signalled to reopen
+// its logs may still finish a few writes into the renamed file.
+fn compress_rotated(path: &Path) {
+    for generation in generations(path).iter().skip(1) {
+        let idle = std::fs::metadata(generation)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|elapsed| elapsed >= COMPRESS_AFTER);
+        if idle && generation.extension().is_none_or(|extension| extension != "gz") {
+            if let Err(e) = compress(generation) {
+                println!("Failed to compress {}: {}", generation.display(), e);
+            }
+        }
+    }
+}
+
+// When the log was started: the previous rotation, or the file's creation
+fn started(path: &Path) -> Option<SystemTime> {
+    let previous = rotated_path(path, 1);
+    std::fs::metadata(&previous)
+        .or_else(|_| std::fs::metadata(gz_path(&previous)))
+        .and_then(|metadata| metadata.modified())
+        .or_else(|_| std::fs::metadata(path).and_then(|metadata| metadata.created()))
+        .ok()
+}
+
+fn due(path: &Path, policy: &Policy) -> bool {
+    let size = match std::fs::metadata(path) {
+        Ok(metadata) => metadata.len(),
+        Err(_) => return false,
+    };
+    if size == 0 {
+        return false;
+    }
+    if policy.max_bytes > 0 && size >= policy.max_bytes {
+        return true;
+    }
+    policy.max_age.is_some_and(|max_age| {
+        started(path)
+            .and_then(|started| started.elapsed().ok())
+            .is_some_and(|age| age >= max_age)
+    })
+}
+
+// Rotate the service's logs that are due, or all of them with `force`.
+// Returns the rotated paths.
+fn rotate_service(base_path: &Path, service: &dyn Service, policy: &Policy, force: bool) -> Result<Vec<PathBuf>, String> {
+    let console = console_log(base_path, service.name());
+    let pid = tracked_pid(service.name())?;
+    let signal = service.reopen_signal().filter(|_| platform::CAN_REOPEN_LOGS);
+
+    let mut rotated = Vec::new();
+    let mut reopen = false;
+    for (_, path) in log_sources(service, base_path) {
+        if !((force && path.exists()) || due(&path, policy)) {
+            continue;
+        }
+        let result = if path == console && capture::is_capturing(&path) {
+            capture::rotate(&path, policy.keep)
+        } else if pid.is_none() || path == console {
+            rotate_by_rename(&path, policy.keep)
+        } else if signal.is_some() {
+            reopen = true;
+            rotate_by_rename(&path, policy.keep)
+        } else {
+            rotate_by_copy(&path, policy.keep)
+        };
+        result.map_err(|e| format!("Failed to rotate {}: {}", path.display(), e))?;
+        rotated.push(path);
+    }
+
+    if let (true, Some(pid), Some(signal)) = (reopen, pid, signal) {
+        platform::reopen_logs(pid, signal)?;
+    }
+    Ok(rotated)
+}
+
+fn rotate_php(base_path: &Path, policy: &Policy, force: bool) -> Result<Vec<PathBuf>, String> {
+    match php_error_log(base_path) {
+        Some(path) if (force && path.exists()) || due(&path, policy) => {
+            rotate_by_rename(&path, policy.keep).map_err(|e| format!("Failed to rotate {}: {}", path.display(), e))?;
+            Ok(vec![path])
+        }
+        _ => Ok(Vec::new()),
+    }
+}
+
+fn rotate_logs_of(base_path: &Path, service: &str, policy: &Policy, force: bool) -> Result<Vec<PathBuf>, String> {
+    match service {
+        "php" => rotate_php(base_path, policy, force),
+        service => rotate_service(base_path, find_service(service)?, policy, force),
+    }
+}
+
+// One pass over every managed log
+pub(crate) fn sweep(base_path: &Path) {
+    let policy = Policy::load(base_path);
+    let services = registry().iter().map(|service| service.name()).chain(["php"]);
+    for service in services {
+        if let Err(e) = rotate_logs_of(base_path, service, &policy, false) {
+            println!("Log rotation for {} failed: {}", service, e);
+        }
+        if policy.compress {
+            for (_, path) in sources_of(base_path, service).unwrap_or_default() {
+                compress_rotated(&path);
+            }
+        }
+    }
+}
+
+// Called once from setup
+pub fn init() {
+    std::thread::spawn(|| loop {
+        if let Ok(base_path) = get_project_root() {
+            sweep(&base_path);
+        }
+        std::thread::sleep(SWEEP_INTERVAL);
+    });
+}
+
+// Rotate all of a service's logs now, whatever their size and age
+#[tauri::command]
+pub async fn rotate_service_logs(service: String) -> Result<Vec<String>, String> {
+    let base_path = get_project_root()?;
+    let policy = Policy::load(&base_path);
+    let rotated = rotate_logs_of(&base_path, &service, &policy, true)?;
+    Ok(rotated.iter().map(|path| path.display().to_string()).collect())
+}
+
+// Empty a service's logs and delete their rotated copies; returns the bytes
+// freed. Running servers keep logging into the emptied files.
+#[tauri::command]
+pub async fn clear_service_logs(service: String) -> Result<u64, String> {
+    let base_path = get_project_root()?;
+    let mut freed = 0;
+    for (_, path) in sources_of(&base_path, &service)? {
+        for generation in generations(&path) {
+            let size = match std::fs::metadata(&generation) {
+                Ok(metadata) => metadata.len(),
+                Err(_) => continue,
+            };
+            let result = if generation != path {
+                std::fs::remove_file(&generation)
+            } else if capture::is_capturing(&path) {
+                capture::truncate(&path)
+            } else {
+                OpenOptions::new().write(true).open(&path).and_then(|file| file.set_len(0))
+            };
+            result.map_err(|e| format!("Failed to clear {}: {}", generation.display(), e))?;
+            freed += size;
+        }
+    }
+    Ok(freed)
+}
@@ -0,0 +1,263 @@
+// Live log tailing. subscribe_logs follows one service's logs, or every
+// service's with "all", and sends the new lines that pass the filter as
+// "log-lines" events until unsubscribe_logs. Each file is followed through its
+// open handle, so lines written just before a rotation are still read; the
+// path is then reopened once it names a new file or shrinks.
+
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{LazyLock, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::watch;
+
+use super::parse::LogFormat;
+use super::{log_services, sources_of, Level};
+use crate::{get_project_root, platform};
+
+pub const LOG_EVENT: &str = "log-lines";
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+// Read at most this much per file and poll, so one flooding log cannot stall the rest
+const MAX_READ_BYTES: usize = 1024 * 1024;
+
+static NEXT_SUBSCRIPTION_ID: AtomicU64 = AtomicU64::new(1);
+static SUBSCRIPTIONS: LazyLock<Mutex<HashMap<u64, watch::Sender<bool>>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Clone, Default, Deserialize)]
+pub struct LogFilter {
+    // Lowest severity to pass; lines without one (continuations such as stack
+    // traces) take the level of the line before them in the same file
+    pub level: Option<Level>,
+    // Case-insensitive substring
+    pub contains: Option<String>,
+    pub regex: Option<String>,
+    // Log names to follow ("error", "access", "console"), all when None
+    pub logs: Option<Vec<String>>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct LogLine {
+    pub service: String,
+    pub log: String,
+    pub level: Option<Level>,
+    pub line: String,
+}
+
+#[derive(Clone, Serialize)]
+pub struct LogLines {
+    pub subscription: u64,
+    pub lines: Vec<LogLine>,
+}
+
+struct Matcher {
+    level: Option<Level>,
+    contains: Option<String>,
+    regex: Option<Regex>,
+}
+
+impl Matcher {
+    fn new(filter: &LogFilter) -> Result<Matcher, String> {
+        let regex = match filter.regex.as_deref().filter(|pattern| !pattern.is_empty()) {
+            Some(pattern) => Some(
+                RegexBuilder::new(pattern)
+                    .size_limit(1 << 20)
+                    .build()
+                    .map_err(|e| format!("Invalid regex: {}", e))?,
+            ),
+            None => None,
+        };
+        Ok(Matcher {
+            level: filter.level,
+            contains: filter.contains.as_deref().filter(|text| !text.is_empty()).map(str::to_lowercase),
+            regex,
+        })
+    }
+
+    fn matches(&self, line: &str, level: Option<Level>) -> bool {
+        if let Some(minimum) = self.level {
+            if level.is_none_or(|level| level < minimum) {
+                return false;
+            }
+        }
+        if let Some(text) = &self.contains {
+            if !line.to_lowercase().contains(text) {
+                return false;
+            }
+        }
+        self.regex.as_ref().is_none_or(|regex| regex.is_match(line))
+    }
+}
+
+// One followed file
+struct Follower {
+    service: String,
+    log: String,
+    path: PathBuf,
+    format: LogFormat,
+    file: Option<File>,
+    id: Option<(u64, u64)>,
+    offset: u64,
+    // Bytes after the last newline, completed by the next read
+    partial: Vec<u8>,
+    level: Option<Level>,
+}
+
+impl Follower {
+    // Start at the current end: subscribers see new lines only
+    fn new(service: &str, log: String, path: PathBuf) -> Follower {
+        let mut follower = Follower {
+            service: service.to_string(),
+            format: LogFormat::of(service, &log),
+            log,
+            path,
+            file: None,
+            id: None,
+            offset: 0,
+            partial: Vec::new(),
+            level: None,
+        };
+        follower.open(true);
+        follower
+    }
+
+    fn open(&mut self, at_end: bool) {
+        self.file = File::open(&self.path).ok();
+        self.level = None;
+        let metadata = self.file.as_ref().and_then(|file| file.metadata().ok());
+        self.id = metadata.as_ref().and_then(platform::file_id);
+        self.offset = match (at_end, &metadata) {
+            (true, Some(metadata)) => metadata.len(),
+            _ => 0,
+        };
+    }
+
+    fn read_available(&mut self, out: &mut Vec<u8>) {
+        let Some(file) = self.file.as_mut() else { return };
+        if file.seek(SeekFrom::Start(self.offset)).is_err() {
+            return;
+        }
+        let mut chunk = Vec::new();
+        if let Ok(read) = file.take(MAX_READ_BYTES as u64).read_to_end(&mut chunk) {
+            self.offset += read as u64;
+            out.extend_from_slice(&chunk);
+        }
+    }
+
+    // The path names a different file than the one we hold: rotated, cleared
+    // or created since the last poll
+    fn replaced(&self) -> bool {
+        let Ok(metadata) = std::fs::metadata(&self.path) else { return false };
+        if self.file.is_none() {
+            return true;
+        }
+        match (self.id, platform::file_id(&metadata)) {
+            (Some(held), Some(current)) if held != current => true,
+            _ => metadata.len() < self.offset,
+        }
+    }
+
+    fn poll(&mut self, matcher: &Matcher, lines: &mut Vec<LogLine>) {
+        let mut bytes = std::mem::take(&mut self.partial);
+        self.read_available(&mut bytes);
+
+        if self.replaced() {
+            // Whatever was left in the old file is complete now
+            if !bytes.is_empty() && !bytes.ends_with(b"\n") {
+                bytes.push(b'\n');
+            }
+            self.open(false);
+            self.read_available(&mut bytes);
+        }
+
+        let complete = bytes.iter().rposition(|byte| *byte == b'\n').map_or(0, |index| index + 1);
+        self.partial = bytes.split_off(complete);
+        for line in super::to_lines(&bytes) {
+            if let Some(level) = self.format.parse(&line).and_then(|record| record.level) {
+                self.level = Some(level);
+            }
+            if matcher.matches(&line, self.level) {
+                lines.push(LogLine {
+                    service: self.service.clone(),
+                    log: self.log.clone(),
+                    level: self.level,
+                    line,
+                });
+            }
+        }
+    }
+}
+
+fn followers(base_path: &Path, service: &str, logs: Option<&[String]>) -> Result<Vec<Follower>, String> {
+    let services = match service {
+        "all" => log_services(),
+        service => vec![service],
+    };
+    let mut followers = Vec::new();
+    for service in services {
+        for (log, path) in sources_of(base_path, service)? {
+            if logs.is_none_or(|logs| logs.contains(&log)) {
+                followers.push(Follower::new(service, log, path));
+            }
+        }
+    }
+    if followers.is_empty() {
+        return Err(format!("{} has no logs to follow", service));
+    }
+    Ok(followers)
+}
+
+async fn follow(app: AppHandle, subscription: u64, mut followers: Vec<Follower>, matcher: Matcher, mut cancel: watch::Receiver<bool>) {
+    loop {
+        let mut lines = Vec::new();
+        for follower in &mut followers {
+            follower.poll(&matcher, &mut lines);
+        }
+        if !lines.is_empty() && app.emit(LOG_EVENT, LogLines { subscription, lines }).is_err() {
+            break;
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(POLL_INTERVAL) => {}
+            _ = cancel.changed() => break,
+        }
+    }
+    if let Ok(mut subscriptions) = SUBSCRIPTIONS.lock() {
+        subscriptions.remove(&subscription);
+    }
+}
+
+// Follow `service`'s logs ("all" for every service, merged) and return the
+// subscription id the "log-lines" events carry
+#[tauri::command]
+pub async fn subscribe_logs(app: AppHandle, service: String, filter: Option<LogFilter>) -> Result<u64, String> {
+    let base_path = get_project_root()?;
+    let filter = filter.unwrap_or_default();
+    let matcher = Matcher::new(&filter)?;
+    let followers = followers(&base_path, &service, filter.logs.as_deref())?;
+
+    let subscription = NEXT_SUBSCRIPTION_ID.fetch_add(1, Ordering::Relaxed);
+    let (cancel, cancel_receiver) = watch::channel(false);
+    SUBSCRIPTIONS.lock().map_err(|e| e.to_string())?.insert(subscription, cancel);
+
+    tokio::spawn(follow(app, subscription, followers, matcher, cancel_receiver));
+    Ok(subscription)
+}
+
+#[tauri::command]
+pub async fn unsubscribe_logs(subscription: u64) -> Result<bool, String> {
+    let cancel = SUBSCRIPTIONS.lock().map_err(|e| e.to_string())?.remove(&subscription);
+    match cancel {
+        Some(cancel) => {
+            cancel.send_replace(true);
+            Ok(true)
+        }
+        None => Err(format!("No log subscription with id {}", subscription)),
+    }
+}
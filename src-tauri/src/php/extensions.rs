@@ -0,0 +1,286 @@
+// PHP extensions per installed version. The bundled ones are the libraries in
+// the version's ext/ directory; php.ini decides which are enabled, and `php -m`
+// shows which actually loaded. A library that is enabled but fails to load
+// (usually a missing DLL/SO dependency) is reported with PHP's own message.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use super::{ini, projects, version_dir};
+use crate::process::{self, Cmd};
+use crate::services::{find_service, reload_if_running, rewrite_lines};
+use crate::{backups, get_project_root, php_binary, platform};
+
+// Loaded with zend_extension= rather than extension=
+const ZEND_EXTENSIONS: &[&str] = &["opcache", "xdebug"];
+
+// `php -m` loads every enabled extension, a broken one can hang it
+const MODULE_REPORT_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Clone, Serialize)]
+pub struct PhpExtension {
+    pub name: String,
+    // Library in ext/, None for extensions php.ini mentions but the build lacks
+    pub file: Option<String>,
+    pub zend: bool,
+    // Enabled in php.ini
+    pub enabled: bool,
+    // Reported by `php -m`
+    pub loaded: bool,
+    pub error: Option<String>,
+}
+
+// "php_mysqli.dll", "mysqli.so", "/path/to/ext/mysqli.so" and "mysqli" all
+// name mysqli; PECL builds carry their version ("php_xdebug-3.3.2-8.3-vs16-x86_64.dll")
+pub(crate) fn extension_name(value: &str) -> String {
+    let value = value.trim().trim_matches('"');
+    let file = value.rsplit(['/', '\\']).next().unwrap_or(value);
+    let stem = file
+        .strip_suffix(".dll")
+        .or_else(|| file.strip_suffix(".so"))
+        .unwrap_or(file);
+    let stem = stem.strip_prefix("php_").unwrap_or(stem);
+    stem.split('-').next().unwrap_or(stem).to_lowercase()
+}
+
+// An extension line in php.ini: name, whether it is active, whether it is a
+// zend_extension. Commented lines only count when the ';' sits right before
+// the directive, as in the stock ";extension=curl" toggles; indented
+// ";   extension=/path/to/..." lines are documentation.
+pub(crate) fn parse_extension_line(line: &str) -> Option<(String, bool, bool)> {
+    let line = line.trim();
+    let (enabled, directive) = match line.strip_prefix(';') {
+        Some(rest) => (false, rest),
+        None => (true, line),
+    };
+    let (key, value) = directive.split_once('=')?;
+    let zend = match key.trim_end() {
+        "extension" => false,
+        "zend_extension" => true,
+        _ => return None,
+    };
+    let value = value.split(';').next().unwrap_or(value).trim();
+    if value.is_empty() {
+        return None;
+    }
+    Some((extension_name(value), enabled, zend))
+}
+
+fn bundled_extensions(php_dir: &Path) -> BTreeMap<String, String> {
+    std::fs::read_dir(ini::ext_dir(php_dir))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| entry.file_name().to_str().map(str::to_string))
+        .filter(|file| file.ends_with(".dll") || file.ends_with(".so"))
+        .map(|file| (extension_name(&file), file))
+        .collect()
+}
+
+struct ModuleReport {
+    loaded: Vec<String>,
+    // Extension name -> PHP's startup error
+    errors: BTreeMap<String, String>,
+}
+
+// Run `php -m` against the version's php.ini
+async fn module_report(php_dir: &Path) -> Result<ModuleReport, String> {
+    let binary = php_binary(php_dir).ok_or("PHP binary not found")?;
+    let cmd = Cmd::new(&binary)
+        .arg("-c")
+        .arg(ini::php_ini_path(php_dir))
+        .arg("-m")
+        .env("PATH", platform::path_with(&[php_dir.to_path_buf()]));
+    let output = process::runner().output(&cmd, MODULE_REPORT_TIMEOUT).await?;
+
+    let stdout = output.stdout;
+    let stderr = output.stderr;
+
+    let loaded = stdout
+        .lines()
+        .map(str::trim)
+        // Section headers and warnings are not module names
+        .filter(|line| !line.is_empty() && !line.starts_with('[') && !line.contains(':'))
+        .map(|module| match module {
+            "Zend OPcache" => "opcache".to_string(),
+            module => module.to_lowercase(),
+        })
+        .collect();
+
+    // Startup warnings go to stdout or stderr depending on display_startup_errors:
+    // "PHP Warning:  PHP Startup: Unable to load dynamic library 'intl' (tried: ...)"
+    let mut errors = BTreeMap::new();
+    for line in stdout.lines().chain(stderr.lines()) {
+        for marker in ["Unable to load dynamic library '", "Failed loading Zend extension '"] {
+            let Some(start) = line.find(marker) else { continue };
+            let rest = &line[start + marker.len()..];
+            if let Some(end) = rest.find('\'') {
+                let message = line[start..].trim_end_matches(" in Unknown on line 0").to_string();
+                errors.insert(extension_name(&rest[..end]), message);
+            }
+        }
+    }
+
+    Ok(ModuleReport { loaded, errors })
+}
+
+fn read_php_ini(php_dir: &Path) -> Result<(PathBuf, String), String> {
+    let path = ini::php_ini_path(php_dir);
+    let content = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    Ok((path, content))
+}
+
+async fn list(php_dir: &Path) -> Result<Vec<PhpExtension>, String> {
+    let (_, content) = read_php_ini(php_dir)?;
+    let bundled = bundled_extensions(php_dir);
+    let report = module_report(php_dir).await?;
+
+    let mut extensions: BTreeMap<String, PhpExtension> = bundled
+        .iter()
+        .map(|(name, file)| {
+            let extension = PhpExtension {
+                name: name.clone(),
+                file: Some(file.clone()),
+                zend: ZEND_EXTENSIONS.contains(&name.as_str()),
+                enabled: false,
+                loaded: false,
+                error: None,
+            };
+            (name.clone(), extension)
+        })
+        .collect();
+
+    for (name, enabled, zend) in content.lines().filter_map(parse_extension_line) {
+        let extension = extensions.entry(name.clone()).or_insert_with(|| PhpExtension {
+            name,
+            file: None,
+            zend,
+            enabled: false,
+            loaded: false,
+            error: None,
+        });
+        extension.enabled |= enabled;
+        extension.zend |= zend;
+    }
+
+    for extension in extensions.values_mut() {
+        extension.loaded = report.loaded.contains(&extension.name);
+        extension.error = report.errors.get(&extension.name).cloned().or_else(|| {
+            match (extension.enabled, extension.loaded, &extension.file) {
+                (true, false, None) => Some(format!("{} is enabled in php.ini but not in {}", extension.name, ini::ext_dir(php_dir).display())),
+                (true, false, Some(_)) => Some(format!("{} is enabled in php.ini but PHP did not load it", extension.name)),
+                _ => None,
+            }
+        });
+    }
+
+    Ok(extensions.into_values().collect())
+}
+
+// php.ini with `name` enabled or disabled. Enabling reuses an existing
+// (commented) line for the extension, otherwise a new line loading `value`
+// goes after the last extension line.
+pub(crate) fn with_extension(content: &str, name: &str, value: &str, zend: bool, enable: bool) -> String {
+    let active = |line: &str| parse_extension_line(line).is_some_and(|(line_name, active, _)| active && line_name == name);
+    if enable && content.lines().any(active) {
+        return content.to_string();
+    }
+
+    let mut done = false;
+    let mut last_extension_line = None;
+    let mut index = 0;
+    let updated = rewrite_lines(content, |line| {
+        index += 1;
+        let (line_name, active, _) = parse_extension_line(line)?;
+        last_extension_line = Some(index);
+        if line_name != name {
+            return None;
+        }
+        match (enable, active) {
+            (true, false) if !done => {
+                done = true;
+                Some(line.trim_start().trim_start_matches(';').to_string())
+            }
+            (false, true) => Some(format!(";{}", line.trim_start())),
+            _ => None,
+        }
+    });
+    if !enable || done {
+        return updated;
+    }
+
+    let directive = format!("{}={}", if zend { "zend_extension" } else { "extension" }, value);
+    let Some(after) = last_extension_line else {
+        let mut content = updated;
+        if !content.is_empty() && !content.ends_with('\n') {
+            content.push('\n');
+        }
+        return content + &directive + "\n";
+    };
+
+    let mut index = 0;
+    rewrite_lines(&updated, |line| {
+        index += 1;
+        (index == after).then(|| format!("{}\n{}", line, directive))
+    })
+}
+
+// Extensions of an installed version, or of the active one when `version` is None
+#[tauri::command]
+pub async fn list_php_extensions(version: Option<String>) -> Result<Vec<PhpExtension>, String> {
+    let base_path = get_project_root()?;
+    list(&version_dir(&base_path, version.as_deref())?).await
+}
+
+// Enabling checks with `php -m` that the extension really loads and puts
+// php.ini back if it does not. Apache is reloaded when running.
+#[tauri::command]
+pub async fn set_php_extension(version: Option<String>, name: String, enabled: bool) -> Result<PhpExtension, String> {
+    let base_path = get_project_root()?;
+    let php_dir = version_dir(&base_path, version.as_deref())?;
+    let name = extension_name(&name);
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(format!("'{}' is not an extension name", name));
+    }
+
+    ini::ensure_php_ini(&base_path, &php_dir)?;
+    let (php_ini, content) = read_php_ini(&php_dir)?;
+
+    let bundled = bundled_extensions(&php_dir);
+    if enabled && !bundled.contains_key(&name) {
+        return Err(format!("{} is not in {}", name, ini::ext_dir(&php_dir).display()));
+    }
+    let zend = ZEND_EXTENSIONS.contains(&name.as_str())
+        || content
+            .lines()
+            .filter_map(parse_extension_line)
+            .any(|(line_name, _, zend)| zend && line_name == name);
+
+    // PHP finds php_<name>.dll and <name>.so by name, anything else by file name
+    let value = match bundled.get(&name) {
+        Some(file) if *file != format!("php_{}.dll", name) && *file != format!("{}.so", name) => file.clone(),
+        _ => name.clone(),
+    };
+
+    let action = if enabled { "Enabled" } else { "Disabled" };
+    let updated = with_extension(&content, &name, &value, zend, enabled);
+    backups::write_config_file(&base_path, &php_ini, &updated, &format!("{} extension {}", action, name))?;
+
+    let extension = list(&php_dir)
+        .await?
+        .into_iter()
+        .find(|extension| extension.name == name)
+        .ok_or_else(|| format!("Extension {} not found", name))?;
+    if enabled && !extension.loaded {
+        backups::write_config_file(&base_path, &php_ini, &content, &format!("Rolled back enabling {}", name))?;
+        return Err(extension
+            .error
+            .unwrap_or_else(|| format!("PHP did not load {}", name)));
+    }
+
+    reload_if_running(find_service("apache")?).await?;
+    projects::restart_pool(&base_path, &php_dir).await?;
+    Ok(extension)
+}
@@ -0,0 +1,598 @@
+// php.ini helpers and the structured editor. Edits work on the text so
+// comments, ordering and the stock documentation blocks in php.ini-development
+// survive; the editor only validates values for the directives it knows.
+
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use super::{projects, switch, version_dir};
+use crate::services::{find_service, reload_if_running, rewrite_lines};
+use crate::{backups, get_project_root, php_binary};
+
+// php.ini sits next to the binary (php/8.3/php.ini or php/8.3/bin/php.ini)
+pub(crate) fn php_ini_path(php_dir: &Path) -> PathBuf {
+    php_binary(php_dir)
+        .and_then(|binary| binary.parent().map(Path::to_path_buf))
+        .unwrap_or_else(|| php_dir.to_path_buf())
+        .join("php.ini")
+}
+
+// Extension directory of a build: ext/ next to php.ini, or in the version root
+pub(crate) fn ext_dir(php_dir: &Path) -> PathBuf {
+    let beside_ini = php_ini_path(php_dir).parent().unwrap_or(php_dir).join("ext");
+    if beside_ini.exists() {
+        beside_ini
+    } else {
+        php_dir.join("ext")
+    }
+}
+
+// Name of the directive on an active (uncommented) line
+fn directive_name(line: &str) -> Option<&str> {
+    let line = line.trim();
+    if line.starts_with(';') || line.starts_with('[') {
+        return None;
+    }
+    line.split_once('=').map(|(name, _)| name.trim())
+}
+
+// Set `key = value`: active assignments are replaced (PHP uses the last one,
+// so all of them), otherwise the line goes below the commented-out default
+// (";extension_dir = "ext""), or at the end when php.ini does not mention the
+// directive at all
+pub(crate) fn set_directive(content: &str, key: &str, value: &str) -> String {
+    let assignment = format!("{} = {}", key, value);
+
+    let mut replaced = false;
+    let updated = rewrite_lines(content, |line| {
+        if directive_name(line) == Some(key) {
+            replaced = true;
+            Some(assignment.clone())
+        } else {
+            None
+        }
+    });
+    if replaced {
+        return updated;
+    }
+
+    let mut inserted = false;
+    let updated = rewrite_lines(content, |line| {
+        let commented = line.trim().strip_prefix(';').and_then(|rest| rest.split_once('='));
+        if !inserted && commented.is_some_and(|(name, _)| name.trim() == key) {
+            inserted = true;
+            Some(format!("{}\n{}", line, assignment))
+        } else {
+            None
+        }
+    });
+    if inserted {
+        return updated;
+    }
+
+    let mut content = content.to_string();
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push_str(&assignment);
+    content.push('\n');
+    content
+}
+
+// Value of the last active assignment of `key` (the one PHP uses), unquoted
+// and without a trailing comment
+pub(crate) fn get_directive(content: &str, key: &str) -> Option<String> {
+    content
+        .lines()
+        .filter(|line| directive_name(line) == Some(key))
+        .filter_map(|line| line.split_once('=').map(|(_, value)| value.trim()))
+        .map(|value| match value.strip_prefix('"') {
+            Some(quoted) => quoted.split('"').next().unwrap_or(quoted).to_string(),
+            None => value.split(';').next().unwrap_or(value).trim().to_string(),
+        })
+        .next_back()
+}
+
+// The version's php.ini, created from php.ini-development when it is missing
+pub(crate) fn ensure_php_ini(base_path: &Path, php_dir: &Path) -> Result<PathBuf, String> {
+    let path = php_ini_path(php_dir);
+    if !path.exists() {
+        let label = php_dir.file_name().and_then(|name| name.to_str()).unwrap_or("current");
+        switch::prepare_php_ini(base_path, php_dir, label)?;
+    }
+    Ok(path)
+}
+
+#[derive(Serialize)]
+pub struct IniEntry {
+    // "directive", "comment" or "blank"
+    kind: &'static str,
+    // 1-based line number in php.ini
+    line: usize,
+    key: Option<String>,
+    value: Option<String>,
+    text: String,
+}
+
+#[derive(Serialize)]
+pub struct IniSection {
+    // "" for the lines before the first [section] header
+    name: String,
+    entries: Vec<IniEntry>,
+}
+
+#[derive(Clone, Copy)]
+enum DirectiveKind {
+    // "128M", "-1" only where PHP accepts it as "no limit"
+    Size { unlimited: bool },
+    Integer { min: i64, max: i64 },
+    Boolean,
+    // "E_ALL & ~E_DEPRECATED"
+    ErrorLevel,
+    Timezone,
+}
+
+struct KnownDirective {
+    key: &'static str,
+    kind: DirectiveKind,
+    description: &'static str,
+}
+
+// Directives the editor offers typed controls and validation for
+const KNOWN_DIRECTIVES: &[KnownDirective] = &[
+    KnownDirective {
+        key: "memory_limit",
+        kind: DirectiveKind::Size { unlimited: true },
+        description: "Maximum memory one script may use, -1 for no limit",
+    },
+    KnownDirective {
+        key: "upload_max_filesize",
+        kind: DirectiveKind::Size { unlimited: false },
+        description: "Largest file that can be uploaded",
+    },
+    KnownDirective {
+        key: "post_max_size",
+        kind: DirectiveKind::Size { unlimited: false },
+        description: "Largest POST body, must be at least upload_max_filesize",
+    },
+    KnownDirective {
+        key: "max_execution_time",
+        kind: DirectiveKind::Integer { min: 0, max: 86_400 },
+        description: "Seconds a script may run, 0 for no limit",
+    },
+    KnownDirective {
+        key: "max_input_time",
+        kind: DirectiveKind::Integer { min: -1, max: 86_400 },
+        description: "Seconds a script may spend parsing input, -1 to use max_execution_time",
+    },
+    KnownDirective {
+        key: "max_input_vars",
+        kind: DirectiveKind::Integer { min: 1, max: 1_000_000 },
+        description: "Maximum number of input variables per request",
+    },
+    KnownDirective {
+        key: "display_errors",
+        kind: DirectiveKind::Boolean,
+        description: "Show errors in the page output",
+    },
+    KnownDirective {
+        key: "display_startup_errors",
+        kind: DirectiveKind::Boolean,
+        description: "Show errors raised while PHP starts up",
+    },
+    KnownDirective {
+        key: "log_errors",
+        kind: DirectiveKind::Boolean,
+        description: "Write errors to the error log",
+    },
+    KnownDirective {
+        key: "error_reporting",
+        kind: DirectiveKind::ErrorLevel,
+        description: "Which errors are reported",
+    },
+    KnownDirective {
+        key: "date.timezone",
+        kind: DirectiveKind::Timezone,
+        description: "Default timezone for date functions",
+    },
+    KnownDirective {
+        key: "expose_php",
+        kind: DirectiveKind::Boolean,
+        description: "Send the X-Powered-By header",
+    },
+];
+
+#[derive(Serialize)]
+pub struct TypedDirective {
+    key: &'static str,
+    // "size", "integer", "boolean", "error_level" or "timezone"
+    kind: &'static str,
+    description: &'static str,
+    min: Option<i64>,
+    max: Option<i64>,
+    // Current value, None when php.ini leaves it at PHP's built-in default
+    value: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct PhpIni {
+    pub path: String,
+    pub sections: Vec<IniSection>,
+    pub directives: Vec<TypedDirective>,
+}
+
+struct Preset {
+    name: &'static str,
+    description: &'static str,
+    directives: &'static [(&'static str, &'static str)],
+}
+
+const PRESETS: &[Preset] = &[
+    Preset {
+        name: "development",
+        description: "Show every error, generous limits",
+        directives: &[
+            ("display_errors", "On"),
+            ("display_startup_errors", "On"),
+            ("log_errors", "On"),
+            ("error_reporting", "E_ALL"),
+            ("memory_limit", "256M"),
+            ("max_execution_time", "120"),
+        ],
+    },
+    Preset {
+        name: "production-like",
+        description: "Log errors instead of showing them, production limits",
+        directives: &[
+            ("display_errors", "Off"),
+            ("display_startup_errors", "Off"),
+            ("log_errors", "On"),
+            ("error_reporting", "E_ALL & ~E_DEPRECATED"),
+            ("expose_php", "Off"),
+            ("memory_limit", "128M"),
+            ("max_execution_time", "30"),
+        ],
+    },
+    Preset {
+        name: "large-uploads",
+        description: "Accept uploads up to 512M",
+        directives: &[
+            ("upload_max_filesize", "512M"),
+            ("post_max_size", "512M"),
+            ("memory_limit", "512M"),
+            ("max_execution_time", "300"),
+            ("max_input_time", "300"),
+        ],
+    },
+];
+
+#[derive(Serialize)]
+pub struct IniPreset {
+    name: &'static str,
+    description: &'static str,
+    directives: Vec<(&'static str, &'static str)>,
+}
+
+const ERROR_CONSTANTS: &[&str] = &[
+    "E_ALL", "E_ERROR", "E_WARNING", "E_PARSE", "E_NOTICE", "E_CORE_ERROR", "E_CORE_WARNING",
+    "E_COMPILE_ERROR", "E_COMPILE_WARNING", "E_USER_ERROR", "E_USER_WARNING", "E_USER_NOTICE",
+    "E_STRICT", "E_RECOVERABLE_ERROR", "E_DEPRECATED", "E_USER_DEPRECATED",
+];
+
+const TIMEZONE_AREAS: &[&str] = &[
+    "Africa", "America", "Antarctica", "Arctic", "Asia", "Atlantic", "Australia", "Europe", "Indian", "Pacific", "Etc",
+];
+
+fn parse_sections(content: &str) -> Vec<IniSection> {
+    let mut sections = vec![IniSection { name: String::new(), entries: Vec::new() }];
+    for (index, line) in content.lines().enumerate() {
+        let trimmed = line.trim();
+        if let Some(name) = trimmed.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+            sections.push(IniSection { name: name.to_string(), entries: Vec::new() });
+            continue;
+        }
+
+        let (kind, key, value) = match directive_name(line) {
+            Some(key) => ("directive", Some(key.to_string()), get_directive(line, key)),
+            None if trimmed.is_empty() => ("blank", None, None),
+            None => ("comment", None, None),
+        };
+        if let Some(section) = sections.last_mut() {
+            section.entries.push(IniEntry { kind, line: index + 1, key, value, text: line.to_string() });
+        }
+    }
+
+    // Drop the unnamed section when the file starts with a header
+    if sections[0].entries.is_empty() {
+        sections.remove(0);
+    }
+    sections
+}
+
+fn typed_directives(content: &str) -> Vec<TypedDirective> {
+    KNOWN_DIRECTIVES
+        .iter()
+        .map(|known| {
+            let (kind, min, max) = match known.kind {
+                DirectiveKind::Size { .. } => ("size", None, None),
+                DirectiveKind::Integer { min, max } => ("integer", Some(min), Some(max)),
+                DirectiveKind::Boolean => ("boolean", None, None),
+                DirectiveKind::ErrorLevel => ("error_level", None, None),
+                DirectiveKind::Timezone => ("timezone", None, None),
+            };
+            TypedDirective {
+                key: known.key,
+                kind,
+                description: known.description,
+                min,
+                max,
+                value: get_directive(content, known.key),
+            }
+        })
+        .collect()
+}
+
+// "128M" -> bytes
+fn size_bytes(value: &str) -> Option<u64> {
+    let value = value.trim();
+    let (digits, unit) = match value.char_indices().last()? {
+        (index, unit) if unit.is_ascii_alphabetic() => (&value[..index], unit.to_ascii_uppercase()),
+        _ => (value, ' '),
+    };
+    let number: u64 = digits.parse().ok()?;
+    let multiplier = match unit {
+        ' ' => 1,
+        'K' => 1 << 10,
+        'M' => 1 << 20,
+        'G' => 1 << 30,
+        _ => return None,
+    };
+    number.checked_mul(multiplier)
+}
+
+// Check a value for a known directive and bring it into php.ini's usual spelling
+fn normalize_value(key: &str, kind: DirectiveKind, value: &str) -> Result<String, String> {
+    let value = value.trim().trim_matches('"');
+    match kind {
+        DirectiveKind::Size { unlimited } => {
+            if unlimited && value == "-1" {
+                return Ok(value.to_string());
+            }
+            if size_bytes(value).is_none() {
+                return Err(format!("{} must be a size like 128M{}", key, if unlimited { " or -1" } else { "" }));
+            }
+            Ok(value.to_ascii_uppercase())
+        }
+        DirectiveKind::Integer { min, max } => match value.parse::<i64>() {
+            Ok(number) if (min..=max).contains(&number) => Ok(number.to_string()),
+            _ => Err(format!("{} must be a whole number between {} and {}", key, min, max)),
+        },
+        DirectiveKind::Boolean => match value.to_ascii_lowercase().as_str() {
+            "on" | "1" | "true" | "yes" => Ok("On".to_string()),
+            "off" | "0" | "false" | "no" => Ok("Off".to_string()),
+            // display_errors can also send errors to one stream
+            stream @ ("stderr" | "stdout") if key == "display_errors" => Ok(stream.to_string()),
+            _ => Err(format!("{} must be On or Off", key)),
+        },
+        DirectiveKind::ErrorLevel => {
+            let tokens = value.split(|c: char| c.is_whitespace() || "&|^~()".contains(c));
+            let valid = tokens
+                .filter(|token| !token.is_empty())
+                .all(|token| ERROR_CONSTANTS.contains(&token) || token.chars().all(|c| c.is_ascii_digit()));
+            if value.is_empty() || !valid {
+                return Err(format!("{} must combine E_* constants, like E_ALL & ~E_DEPRECATED", key));
+            }
+            Ok(value.to_string())
+        }
+        DirectiveKind::Timezone => {
+            let area = value.split('/').next().unwrap_or_default();
+            let valid = value == "UTC"
+                || (value.contains('/')
+                    && TIMEZONE_AREAS.contains(&area)
+                    && value.chars().all(|c| c.is_ascii_alphanumeric() || "/_-+".contains(c)));
+            if !valid {
+                return Err(format!("'{}' is not a timezone like Europe/Berlin or UTC", value));
+            }
+            Ok(value.to_string())
+        }
+    }
+}
+
+fn validate_directive(key: &str, value: &str) -> Result<String, String> {
+    if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.') {
+        return Err(format!("'{}' is not a php.ini directive name", key));
+    }
+    if key == "extension" || key == "zend_extension" {
+        return Err("Extensions are managed with set_php_extension".to_string());
+    }
+    match KNOWN_DIRECTIVES.iter().find(|known| known.key == key) {
+        Some(known) => normalize_value(key, known.kind, value),
+        None if value.contains(['\n', '\r']) => Err(format!("The value for {} cannot span lines", key)),
+        None => Ok(value.trim().to_string()),
+    }
+}
+
+// Limits that only make sense together
+fn check_consistency(content: &str) -> Result<(), String> {
+    let size = |key| get_directive(content, key).and_then(|value| size_bytes(&value));
+    if let (Some(upload), Some(post)) = (size("upload_max_filesize"), size("post_max_size")) {
+        // post_max_size = 0 means no limit
+        if post != 0 && upload > post {
+            return Err("upload_max_filesize cannot be larger than post_max_size".to_string());
+        }
+    }
+    Ok(())
+}
+
+fn read(php_dir: &Path) -> Result<PhpIni, String> {
+    let path = php_ini_path(php_dir);
+    let content = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    Ok(PhpIni {
+        path: path.display().to_string(),
+        sections: parse_sections(&content),
+        directives: typed_directives(&content),
+    })
+}
+
+// Apply validated `changes` to the version's php.ini and reload Apache so it
+// sees them
+async fn apply(version: Option<String>, changes: &[(&str, &str)], reason: &str) -> Result<PhpIni, String> {
+    let base_path = get_project_root()?;
+    let php_dir = version_dir(&base_path, version.as_deref())?;
+    let path = ensure_php_ini(&base_path, &php_dir)?;
+
+    let mut content = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    for (key, value) in changes {
+        let value = validate_directive(key, value)?;
+        content = set_directive(&content, key, &value);
+    }
+    check_consistency(&content)?;
+
+    backups::write_config_file(&base_path, &path, &content, reason)?;
+    reload_if_running(find_service("apache")?).await?;
+    projects::restart_pool(&base_path, &php_dir).await?;
+    read(&php_dir)
+}
+
+// php.ini of an installed version (a `version` from get_php_versions), or of
+// the active one when None
+#[tauri::command]
+pub async fn get_php_ini(version: Option<String>) -> Result<PhpIni, String> {
+    let base_path = get_project_root()?;
+    let php_dir = version_dir(&base_path, version.as_deref())?;
+    ensure_php_ini(&base_path, &php_dir)?;
+    read(&php_dir)
+}
+
+#[tauri::command]
+pub async fn set_php_ini_directive(version: Option<String>, key: String, value: String) -> Result<PhpIni, String> {
+    apply(version, &[(key.as_str(), value.as_str())], &format!("Set {}", key)).await
+}
+
+#[tauri::command]
+pub async fn list_php_ini_presets() -> Result<Vec<IniPreset>, String> {
+    Ok(PRESETS
+        .iter()
+        .map(|preset| IniPreset {
+            name: preset.name,
+            description: preset.description,
+            directives: preset.directives.to_vec(),
+        })
+        .collect())
+}
+
+#[tauri::command]
+pub async fn apply_php_ini_preset(version: Option<String>, name: String) -> Result<PhpIni, String> {
+    let preset = PRESETS
+        .iter()
+        .find(|preset| preset.name == name)
+        .ok_or_else(|| format!("Unknown php.ini preset: {}", name))?;
+    apply(version, preset.directives, &format!("Applied the {} preset", preset.name)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PHP_INI: &str = "[PHP]\n; Maximum amount of memory a script may consume\n; http://php.net/memory-limit\nmemory_limit = 128M\n\n;extension_dir = \"ext\"\n\n[Date]\n;date.timezone =\n";
+
+    #[test]
+    fn set_directive_replaces_inserts_or_appends() {
+        let cases: &[(&str, &str, &str, &str)] = &[
+            // Active assignment replaced in place
+            ("memory_limit", "512M", "memory_limit = 128M\n", "memory_limit = 512M\n"),
+            // PHP uses the last one, so every active one changes
+            ("memory_limit", "1G", "memory_limit = 128M\nmemory_limit=256M\n", "memory_limit = 1G\nmemory_limit = 1G\n"),
+            // Below the commented-out default
+            ("extension_dir", "\"ext\"", ";extension_dir = \"ext\"\nfoo = 1\n", ";extension_dir = \"ext\"\nextension_dir = \"ext\"\nfoo = 1\n"),
+            // Appended when php.ini does not mention it
+            ("expose_php", "Off", "foo = 1\n", "foo = 1\nexpose_php = Off\n"),
+            ("expose_php", "Off", "foo = 1", "foo = 1\nexpose_php = Off\n"),
+            ("expose_php", "Off", "", "expose_php = Off\n"),
+            // Windows line endings survive the rewrite
+            ("memory_limit", "256M", "memory_limit = 128M\r\nfoo = 1\r\n", "memory_limit = 256M\r\nfoo = 1\r\n"),
+            // A directive whose name only starts with the key is another one
+            ("memory", "1", "memory_limit = 128M\n", "memory_limit = 128M\nmemory = 1\n"),
+        ];
+        for (key, value, content, expected) in cases {
+            assert_eq!(set_directive(content, key, value), *expected, "{} = {} in {:?}", key, value, content);
+        }
+
+        let updated = set_directive(PHP_INI, "date.timezone", "Europe/Berlin");
+        assert!(updated.ends_with("[Date]\n;date.timezone =\ndate.timezone = Europe/Berlin\n"));
+        assert_eq!(get_directive(&updated, "date.timezone").as_deref(), Some("Europe/Berlin"));
+    }
+
+    #[test]
+    fn get_directive_reads_the_effective_value() {
+        let cases: &[(&str, &str, Option<&str>)] = &[
+            ("memory_limit", PHP_INI, Some("128M")),
+            // Commented defaults are not values
+            ("extension_dir", PHP_INI, None),
+            ("date.timezone", PHP_INI, None),
+            ("memory_limit", "memory_limit = 128M\nmemory_limit = 256M\n", Some("256M")),
+            ("extension_dir", "extension_dir = \"C:/php/ext\" ; quoted\n", Some("C:/php/ext")),
+            ("display_errors", "display_errors = On ; for development\n", Some("On")),
+            ("error_log", "error_log=\n", Some("")),
+            ("memory_limit", "  memory_limit   =   64M  \n", Some("64M")),
+        ];
+        for (key, content, expected) in cases {
+            assert_eq!(get_directive(content, key).as_deref(), *expected, "{} in {:?}", key, content);
+        }
+    }
+
+    #[test]
+    fn typed_values_are_validated_and_normalized() {
+        let cases: &[(&str, &str, Result<&str, ()>)] = &[
+            ("memory_limit", "256m", Ok("256M")),
+            ("memory_limit", "-1", Ok("-1")),
+            ("memory_limit", "\"1G\"", Ok("1G")),
+            ("memory_limit", "lots", Err(())),
+            ("upload_max_filesize", "-1", Err(())),
+            ("upload_max_filesize", "64M", Ok("64M")),
+            ("post_max_size", "8T", Err(())),
+            ("max_execution_time", "300", Ok("300")),
+            ("max_execution_time", "-1", Err(())),
+            ("max_execution_time", "86401", Err(())),
+            ("max_input_time", "-1", Ok("-1")),
+            ("max_input_vars", "0", Err(())),
+            ("display_errors", "on", Ok("On")),
+            ("display_errors", "0", Ok("Off")),
+            ("display_errors", "stderr", Ok("stderr")),
+            ("log_errors", "stderr", Err(())),
+            ("expose_php", "maybe", Err(())),
+            ("error_reporting", "E_ALL & ~E_DEPRECATED & ~E_STRICT", Ok("E_ALL & ~E_DEPRECATED & ~E_STRICT")),
+            ("error_reporting", "32767", Ok("32767")),
+            ("error_reporting", "E_EVERYTHING", Err(())),
+            ("error_reporting", "", Err(())),
+            ("date.timezone", "Europe/Berlin", Ok("Europe/Berlin")),
+            ("date.timezone", "America/Argentina/Buenos_Aires", Ok("America/Argentina/Buenos_Aires")),
+            ("date.timezone", "UTC", Ok("UTC")),
+            ("date.timezone", "Mars/Olympus", Err(())),
+            ("date.timezone", "Berlin", Err(())),
+            // Directives without a type only have to fit on one line
+            ("session.save_path", " /tmp/sessions ", Ok("/tmp/sessions")),
+            ("session.save_path", "/tmp\nmemory_limit = -1", Err(())),
+            ("extension", "gd", Err(())),
+            ("zend_extension", "xdebug", Err(())),
+            ("bad key", "1", Err(())),
+            ("", "1", Err(())),
+        ];
+        for (key, value, expected) in cases {
+            let result = validate_directive(key, value);
+            assert_eq!(result.as_deref().map_err(|_| ()), *expected, "{} = {:?}: {:?}", key, value, result);
+        }
+    }
+
+    #[test]
+    fn upload_limit_cannot_exceed_post_limit() {
+        assert!(check_consistency("upload_max_filesize = 64M\npost_max_size = 32M\n").is_err());
+        assert!(check_consistency("upload_max_filesize = 32M\npost_max_size = 32M\n").is_ok());
+        // post_max_size = 0 means no limit
+        assert!(check_consistency("upload_max_filesize = 2G\npost_max_size = 0\n").is_ok());
+        assert!(check_consistency("upload_max_filesize = 2G\n").is_ok());
+        assert_eq!(size_bytes("2k"), Some(2048));
+        assert_eq!(size_bytes("1G"), Some(1 << 30));
+        assert_eq!(size_bytes("12X"), None);
+    }
+}
@@ -0,0 +1,410 @@
+// PHP installer. Streams the release archive into php/.staging, checks its
+// SHA-256, extracts it next to the final location and only then renames it to
+// php/<version>, so a failed or interrupted install never looks installed.
+
+use sha2::{Digest, Sha256};
+use std::cmp::Ordering;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+
+use super::releases;
+use crate::jobs::JobHandle;
+use crate::{php_binary, platform};
+
+// windows.php.net only publishes Windows builds. Elsewhere PHP comes from the
+// system packages; a build copied or linked into php/<version> is picked up.
+pub(crate) fn check_platform() -> Result<(), String> {
+    if cfg!(windows) {
+        Ok(())
+    } else {
+        Err(format!(
+            "PHP downloads are Windows builds and cannot run on {}; install PHP with the system package manager and link it into php/<version>",
+            std::env::consts::OS
+        ))
+    }
+}
+
+// Release file name for a full patch version, e.g. php-8.3.14-nts-Win32-vs16-x64.zip.
+// Used when the releases index does not know the version (older patches).
+pub(crate) fn archive_name(version: &str, thread_safe: bool, arch: &str) -> String {
+    // PHP 8.4 moved to the Visual Studio 17 runtime
+    let compiler = if releases::compare_versions(version, "8.4") == Ordering::Less { "vs16" } else { "vs17" };
+    let flavour = if thread_safe { "" } else { "nts-" };
+    format!("php-{}-{}Win32-{}-{}.zip", version, flavour, compiler, arch)
+}
+
+pub(crate) fn download_url(mirror: &str, version: &str, thread_safe: bool, arch: &str) -> String {
+    format!("{}/{}", mirror.trim_end_matches('/'), archive_name(version, thread_safe, arch))
+}
+
+// Versions end up in paths, so only "8.3" or "8.3.14" style strings are allowed
+pub(crate) fn validate_version(version: &str) -> Result<(), String> {
+    let valid = !version.is_empty()
+        && version.split('.').all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid PHP version: {}", version))
+    }
+}
+
+pub(crate) fn client() -> Result<reqwest::Client, String> {
+    reqwest::Client::builder()
+        .user_agent(concat!("DevStackBox/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+// Find the checksum for `archive` in a sha256sum.txt listing. Accepts the
+// coreutils format ("<hash> *<file>" or "<hash>  <file>") and the BSD one
+// ("SHA256 (<file>) = <hash>").
+fn find_checksum(listing: &str, archive: &str) -> Option<String> {
+    listing.lines().find_map(|line| {
+        let line = line.trim();
+        let (hash, file) = match line.strip_prefix("SHA256 (") {
+            Some(rest) => {
+                let (file, hash) = rest.split_once(") = ")?;
+                (hash, file)
+            }
+            None => {
+                let (hash, file) = line.split_once(char::is_whitespace)?;
+                (hash, file.trim_start().trim_start_matches('*'))
+            }
+        };
+        let valid = hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit());
+        (valid && file == archive).then(|| hash.to_ascii_lowercase())
+    })
+}
+
+// Releases publish a sha256sum.txt next to the archives
+async fn published_checksum(client: &reqwest::Client, url: &str) -> Result<String, String> {
+    let (directory, archive) = url.rsplit_once('/').ok_or_else(|| format!("Invalid download URL: {}", url))?;
+    let listing_url = format!("{}/sha256sum.txt", directory);
+
+    let listing = client
+        .get(&listing_url)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("Failed to fetch checksums from {}: {}", listing_url, e))?
+        .text()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    find_checksum(&listing, archive).ok_or_else(|| format!("{} is not listed in {}", archive, listing_url))
+}
+
+// Stream the archive to disk, hashing it on the way, and return the digest
+async fn download(client: &reqwest::Client, url: &str, target: &Path, job: &JobHandle) -> Result<String, String> {
+    let mut response = client
+        .get(url)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("Failed to download {}: {}", url, e))?;
+
+    let total = response.content_length();
+    let mut file = tokio::fs::File::create(target).await.map_err(|e| e.to_string())?;
+    let mut hasher = Sha256::new();
+    let mut received = 0;
+
+    while let Some(chunk) = response.chunk().await.map_err(|e| format!("Download interrupted: {}", e))? {
+        hasher.update(&chunk);
+        file.write_all(&chunk).await.map_err(|e| e.to_string())?;
+        received += chunk.len() as u64;
+        job.progress(received, total);
+    }
+    file.flush().await.map_err(|e| e.to_string())?;
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+fn extract_zip(archive: &Path, destination: &Path, job: &JobHandle) -> Result<(), String> {
+    let file = File::open(archive).map_err(|e| e.to_string())?;
+    let mut zip = zip::ZipArchive::new(file).map_err(|e| format!("Invalid archive: {}", e))?;
+
+    let count = zip.len() as u64;
+    for index in 0..zip.len() {
+        job.check_cancelled()?;
+        job.progress(index as u64, Some(count));
+        let mut entry = zip.by_index(index).map_err(|e| format!("Invalid archive: {}", e))?;
+        // Reject entries that would escape the destination ("../", absolute paths)
+        let relative = entry
+            .enclosed_name()
+            .ok_or_else(|| format!("Archive contains an unsafe path: {}", entry.name()))?;
+        let target = destination.join(relative);
+
+        if entry.is_dir() {
+            std::fs::create_dir_all(&target).map_err(|e| e.to_string())?;
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let mut output = File::create(&target).map_err(|e| e.to_string())?;
+        std::io::copy(&mut entry, &mut output).map_err(|e| format!("Failed to extract {}: {}", entry.name(), e))?;
+
+        #[cfg(unix)]
+        if let Some(mode) = entry.unix_mode() {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&target, std::fs::Permissions::from_mode(mode)).map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(())
+}
+
+// Windows builds are flat, other archives wrap everything in one folder
+fn package_root(extracted: &Path) -> Result<PathBuf, String> {
+    if php_binary(extracted).is_some() {
+        return Ok(extracted.to_path_buf());
+    }
+
+    let entries: Vec<PathBuf> = std::fs::read_dir(extracted)
+        .map_err(|e| e.to_string())?
+        .flatten()
+        .map(|entry| entry.path())
+        .collect();
+    match entries.as_slice() {
+        [single] if single.is_dir() && php_binary(single).is_some() => Ok(single.clone()),
+        _ => Err(format!("The archive does not contain a {} binary", platform::exe_name("php"))),
+    }
+}
+
+// Fresh installs start from the development defaults. Windows builds ship
+// php.ini-development next to php.exe, other layouts may keep it in bin/.
+fn seed_php_ini(php_dir: &Path) -> Result<(), String> {
+    let binary_dir = php_binary(php_dir).and_then(|binary| binary.parent().map(Path::to_path_buf));
+    let ini_dir = [Some(php_dir.to_path_buf()), binary_dir]
+        .into_iter()
+        .flatten()
+        .find(|dir| dir.join("php.ini-development").exists());
+
+    if let Some(dir) = ini_dir.filter(|dir| !dir.join("php.ini").exists()) {
+        std::fs::copy(dir.join("php.ini-development"), dir.join("php.ini"))
+            .map_err(|e| format!("Failed to create php.ini: {}", e))?;
+    }
+    Ok(())
+}
+
+async fn fetch_and_unpack(
+    url: &str,
+    sha256: Option<String>,
+    archive: &Path,
+    extracted: &Path,
+    job: &JobHandle,
+) -> Result<(), String> {
+    let client = client()?;
+    let expected = match sha256 {
+        Some(hash) => hash.trim().to_ascii_lowercase(),
+        None => {
+            job.phase("Fetching checksum");
+            job.cancellable(published_checksum(&client, url)).await?
+        }
+    };
+
+    job.phase("Downloading");
+    // A stalled connection must not keep the user from cancelling
+    let actual = job.cancellable(download(&client, url, archive, job)).await?;
+    if actual != expected {
+        return Err(format!("Checksum mismatch: expected {}, got {}", expected, actual));
+    }
+
+    job.phase("Extracting");
+    let (archive, extracted, extract_job) = (archive.to_path_buf(), extracted.to_path_buf(), job.clone());
+    tokio::task::spawn_blocking(move || extract_zip(&archive, &extracted, &extract_job))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+// Staging files go away however the install ends, including when the job's
+// future is dropped half way
+struct Staging {
+    dir: PathBuf,
+    archive: PathBuf,
+    extracted: PathBuf,
+}
+
+impl Drop for Staging {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.archive);
+        let _ = std::fs::remove_dir_all(&self.extracted);
+        let _ = std::fs::remove_dir(&self.dir);
+    }
+}
+
+// Download `url` and install it as php/<version>. Without an explicit
+// checksum the one published next to the archive is required.
+pub(crate) async fn install(
+    base_path: &Path,
+    version: &str,
+    url: &str,
+    sha256: Option<String>,
+    job: &JobHandle,
+) -> Result<PathBuf, String> {
+    validate_version(version)?;
+    let php_root = base_path.join("php");
+    let php_dir = php_root.join(version);
+    if php_binary(&php_dir).is_some() {
+        return Err(format!("PHP {} is already installed", version));
+    }
+
+    let dir = php_root.join(".staging");
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let staging = Staging {
+        archive: dir.join(format!("php-{}.zip.part", version)),
+        extracted: dir.join(version),
+        dir,
+    };
+    let _ = std::fs::remove_dir_all(&staging.extracted);
+
+    fetch_and_unpack(url, sha256, &staging.archive, &staging.extracted, job).await?;
+    job.phase("Installing");
+    let root = package_root(&staging.extracted)?;
+    seed_php_ini(&root)?;
+
+    // Leftovers of an earlier broken install are not worth keeping
+    if php_dir.exists() {
+        std::fs::remove_dir_all(&php_dir).map_err(|e| e.to_string())?;
+    }
+    std::fs::rename(&root, &php_dir).map_err(|e| format!("Failed to move PHP into place: {}", e))?;
+    Ok(php_dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jobs;
+    use std::io::Write;
+    use std::time::Duration;
+    use tokio::io::AsyncReadExt;
+    use tokio::net::TcpListener;
+
+    // A zip with the layout of a Windows build, or of a Unix one under bin/
+    fn fixture_archive() -> Vec<u8> {
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+        zip.start_file(platform::exe_name("php"), options).unwrap();
+        zip.write_all(b"php").unwrap();
+        zip.start_file("php.ini-development", options).unwrap();
+        zip.write_all(b"memory_limit=128M\n").unwrap();
+        zip.finish().unwrap().into_inner()
+    }
+
+    fn sha256(data: &[u8]) -> String {
+        format!("{:x}", Sha256::digest(data))
+    }
+
+    // Serves `files` by name over HTTP/1.0; a file listed in `stall` gets its
+    // headers and then nothing more, like a connection that went quiet
+    async fn serve(files: Vec<(&'static str, Vec<u8>)>, stall: Option<&'static str>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            loop {
+                let Ok((mut stream, _)) = listener.accept().await else { return };
+                let files = files.clone();
+                tokio::spawn(async move {
+                    let mut request = vec![0u8; 4096];
+                    let read = stream.read(&mut request).await.unwrap_or(0);
+                    let request = String::from_utf8_lossy(&request[..read]).to_string();
+                    let path = request.split_whitespace().nth(1).unwrap_or("/").trim_start_matches('/').to_string();
+
+                    let response = match files.iter().find(|(name, _)| *name == path) {
+                        Some((name, body)) if stall == Some(*name) => {
+                            let header = format!("HTTP/1.0 200 OK\r\nContent-Length: {}\r\n\r\n", body.len() * 100);
+                            let _ = stream.write_all(header.as_bytes()).await;
+                            tokio::time::sleep(Duration::from_secs(60)).await;
+                            return;
+                        }
+                        Some((_, body)) => {
+                            let mut response = format!("HTTP/1.0 200 OK\r\nContent-Length: {}\r\n\r\n", body.len()).into_bytes();
+                            response.extend_from_slice(body);
+                            response
+                        }
+                        None => b"HTTP/1.0 404 Not Found\r\nContent-Length: 0\r\n\r\n".to_vec(),
+                    };
+                    let _ = stream.write_all(&response).await;
+                });
+            }
+        });
+        format!("http://{}", address)
+    }
+
+    fn temp_base(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("devstackbox-install-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    async fn run_install(base_path: &Path, label: &str, url: String, sha256: Option<String>) -> Result<PathBuf, String> {
+        let base_path = base_path.to_path_buf();
+        jobs::run("php-install", label.to_string(), |job| async move {
+            install(&base_path, "8.3.14", &url, sha256, &job).await
+        })
+        .await
+    }
+
+    fn staging_is_gone(base_path: &Path) -> bool {
+        !base_path.join("php").join(".staging").exists()
+    }
+
+    #[tokio::test]
+    async fn installs_with_published_checksum() {
+        let base_path = temp_base("ok");
+        let archive = fixture_archive();
+        let listing = format!("{} *php-8.3.14.zip\n", sha256(&archive)).into_bytes();
+        let mirror = serve(vec![("php-8.3.14.zip", archive), ("sha256sum.txt", listing)], None).await;
+
+        let php_dir = run_install(&base_path, "install ok", format!("{}/php-8.3.14.zip", mirror), None).await.unwrap();
+        assert!(php_binary(&php_dir).is_some());
+        assert!(php_dir.join("php.ini").exists());
+        assert!(staging_is_gone(&base_path));
+        let _ = std::fs::remove_dir_all(&base_path);
+    }
+
+    #[tokio::test]
+    async fn rejects_checksum_mismatch() {
+        let base_path = temp_base("mismatch");
+        let mirror = serve(vec![("php-8.3.14.zip", fixture_archive())], None).await;
+
+        let wrong = sha256(b"something else");
+        let result = run_install(&base_path, "install mismatch", format!("{}/php-8.3.14.zip", mirror), Some(wrong)).await;
+        assert!(result.unwrap_err().starts_with("Checksum mismatch"));
+        assert!(!base_path.join("php").join("8.3.14").exists());
+        assert!(staging_is_gone(&base_path));
+        let _ = std::fs::remove_dir_all(&base_path);
+    }
+
+    #[tokio::test]
+    async fn cancelling_a_stalled_download_cleans_up() {
+        let base_path = temp_base("cancel");
+        let archive = fixture_archive();
+        let checksum = sha256(&archive);
+        let mirror = serve(vec![("php-8.3.14.zip", archive)], Some("php-8.3.14.zip")).await;
+
+        let label = "install cancel";
+        let canceller = tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                let jobs = jobs::list_jobs().await.unwrap();
+                if let Some(job) = jobs.iter().find(|job| job.label == label) {
+                    return jobs::cancel_job(job.id).await;
+                }
+            }
+        });
+
+        let result = tokio::time::timeout(
+            Duration::from_secs(10),
+            run_install(&base_path, label, format!("{}/php-8.3.14.zip", mirror), Some(checksum)),
+        )
+        .await
+        .expect("cancelling did not stop the download");
+        assert_eq!(result.unwrap_err(), "Cancelled");
+        assert_eq!(canceller.await.unwrap(), Ok(true));
+        assert!(!base_path.join("php").join("8.3.14").exists());
+        assert!(staging_is_gone(&base_path));
+        let _ = std::fs::remove_dir_all(&base_path);
+    }
+}
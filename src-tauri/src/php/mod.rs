@@ -0,0 +1,31 @@
+// PHP version management. PHP is not a long-running service, so it lives
+// outside the service registry: versions are installed side by side under
+// php/<version>/ and php/current links to the active one.
+
+pub(crate) mod extensions;
+pub(crate) mod ini;
+pub(crate) mod install;
+pub(crate) mod projects;
+pub(crate) mod releases;
+pub(crate) mod switch;
+pub(crate) mod xdebug;
+
+use std::path::{Path, PathBuf};
+
+// Directory of an installed version, or of the active one (php/current) for None
+pub(crate) fn version_dir(base_path: &Path, version: Option<&str>) -> Result<PathBuf, String> {
+    let php_dir = match version {
+        Some(version) => {
+            install::validate_version(version)?;
+            base_path.join("php").join(version)
+        }
+        None => switch::current_dir(base_path),
+    };
+    if crate::php_binary(&php_dir).is_none() {
+        return Err(match version {
+            Some(version) => format!("PHP {} is not installed", version),
+            None => "No active PHP version".to_string(),
+        });
+    }
+    Ok(php_dir)
+}
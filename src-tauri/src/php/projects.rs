@@ -0,0 +1,405 @@
+// Per-project PHP versions. A project (a directory under the document root)
+// can be pinned to an installed version other than the global one. Each pinned
+// version runs as a php-cgi FastCGI pool on its own port, and Apache hands the
+// project's .php files to that pool through mod_proxy_fcgi. Unpinned projects
+// keep using mod_php from php/current. Pools log to logs/php-cgi-<version>.log
+// and are restarted like services when they crash.
+
+use serde::Serialize;
+use std::collections::hash_map::{Entry, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+use super::releases::{branch_of, compare_versions};
+use super::switch;
+use crate::logs::rotate::Policy;
+use crate::process::{self, Cmd, Exit, Process};
+use crate::services::supervisor::{self, ServiceCrash};
+use crate::services::{config, find_service, reload_if_running};
+use crate::settings::{self, ProjectSettings, Settings};
+use crate::{backups, get_project_root, installed_php_versions, jobs, logs, platform};
+
+// Running pools by installed version
+static POOLS: LazyLock<Mutex<HashMap<String, Pool>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+// How often a pool is checked for having exited
+const POOL_POLL: Duration = Duration::from_secs(2);
+
+struct Pool {
+    port: u16,
+    child: Box<dyn Process>,
+    // Unexpected exits within the supervisor's crash window
+    crashes: Vec<Instant>,
+}
+
+#[derive(Serialize)]
+pub struct ProjectPhp {
+    pub project: String,
+    pub path: String,
+    // What the project is pinned to, None when it follows the global version
+    pub php_version: Option<String>,
+    // The installed version actually serving the project
+    pub effective_version: Option<String>,
+    // "module" (mod_php via php/current) or "fastcgi"
+    pub handler: String,
+    pub fastcgi_port: Option<u16>,
+}
+
+// The installed version a pin refers to: an exact directory name, or the
+// newest installed patch of a branch
+fn resolve_pin(base_path: &Path, pin: &str) -> Option<String> {
+    let installed = installed_php_versions(&base_path.join("php"));
+    installed
+        .iter()
+        .find(|(name, _)| name == pin)
+        .or_else(|| installed.iter().find(|(name, _)| branch_of(name) == pin))
+        .map(|(name, _)| name.clone())
+}
+
+fn cgi_binary(php_dir: &Path) -> Option<PathBuf> {
+    platform::find_executable(php_dir, "php-cgi")
+}
+
+// Pinned versions that are installed, each with the port its pool listens on.
+// Ports are handed out in version order from the configured base port.
+fn pool_ports(base_path: &Path, settings: &Settings) -> Vec<(String, u16)> {
+    let mut versions: Vec<String> = settings
+        .projects
+        .values()
+        .filter_map(|project| resolve_pin(base_path, &project.php_version))
+        .collect();
+    versions.sort_by(|a, b| compare_versions(a, b));
+    versions.dedup();
+
+    versions
+        .into_iter()
+        .zip(settings.php.fastcgi_base_port..)
+        .collect()
+}
+
+// php-projects.conf only takes effect through the drop-in include of
+// httpd.conf. A missing httpd.conf is generated with it on the next start.
+fn check_drop_in_include(base_path: &Path) -> Result<(), String> {
+    let apache = find_service("apache")?;
+    let httpd_conf = apache.config_path(base_path);
+    let Ok(content) = std::fs::read_to_string(&httpd_conf) else { return Ok(()) };
+
+    let include_dir = platform::config_path(&config::include_dir(apache, base_path));
+    let included = content.lines().map(str::trim).any(|line| {
+        let directive = line.split_whitespace().next().unwrap_or_default();
+        (directive.eq_ignore_ascii_case("Include") || directive.eq_ignore_ascii_case("IncludeOptional"))
+            && line.contains(&include_dir)
+    });
+    if included {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} does not include {}/*.conf, which routes projects to their PHP version. Regenerate the Apache config or add that IncludeOptional line.",
+            httpd_conf.display(),
+            include_dir
+        ))
+    }
+}
+
+pub(crate) fn projects_config_path(base_path: &Path) -> Result<PathBuf, String> {
+    Ok(config::include_dir(find_service("apache")?, base_path).join("php-projects.conf"))
+}
+
+// Apache include routing each pinned project to its pool. Sections for
+// FilesMatch are merged in file order, so these override the mod_php handler
+// from httpd.conf for the project's directory only.
+fn render_projects_config(base_path: &Path, settings: &Settings) -> String {
+    let mut config = String::from("# Generated by DevStackBox from the [projects] settings, changes here are overwritten\n");
+    if settings.projects.is_empty() {
+        return config;
+    }
+
+    config.push_str(concat!(
+        "<IfModule !proxy_module>\n    LoadModule proxy_module modules/mod_proxy.so\n</IfModule>\n",
+        "<IfModule !proxy_fcgi_module>\n    LoadModule proxy_fcgi_module modules/mod_proxy_fcgi.so\n</IfModule>\n",
+    ));
+
+    let ports = pool_ports(base_path, settings);
+    let document_root = settings.document_root(base_path);
+    for (project, overrides) in &settings.projects {
+        let port = resolve_pin(base_path, &overrides.php_version)
+            .and_then(|version| ports.iter().find(|(pooled, _)| *pooled == version).map(|(_, port)| *port));
+        let port = match port {
+            Some(port) => port,
+            None => {
+                config.push_str(&format!("\n# {}: PHP {} is not installed\n", project, overrides.php_version));
+                continue;
+            }
+        };
+
+        config.push_str(&format!(
+            "\n# {}: PHP {}\n<Directory \"{}\">\n    <FilesMatch \"\\.php$\">\n        SetHandler \"proxy:fcgi://127.0.0.1:{}\"\n    </FilesMatch>\n</Directory>\n",
+            project,
+            overrides.php_version,
+            platform::config_path(&document_root.join(project)),
+            port
+        ));
+    }
+    config
+}
+
+pub(crate) fn write_projects_config(base_path: &Path, settings: &Settings) -> Result<(), String> {
+    let path = projects_config_path(base_path)?;
+    let content = render_projects_config(base_path, settings);
+    backups::write_config_file(base_path, &path, &content, "Generated from the [projects] settings")
+}
+
+// Name of a pool in logs and crash reports, "php-cgi-8.3.14"
+fn pool_name(version: &str) -> String {
+    format!("php-cgi-{}", version)
+}
+
+// Start a pool with its output captured and a watch on it. Must be called
+// from the async runtime.
+fn spawn_pool(base_path: &Path, version: &str, port: u16, policy: Policy) -> Result<Box<dyn Process>, String> {
+    let php_dir = base_path.join("php").join(version);
+    let binary = cgi_binary(&php_dir).ok_or_else(|| format!("PHP {} has no php-cgi binary", version))?;
+
+    let cmd = Cmd::new(&binary)
+        .arg("-b")
+        .arg(format!("127.0.0.1:{}", port))
+        .arg("-c")
+        .arg(super::ini::php_ini_path(&php_dir))
+        .current_dir(base_path)
+        // php-cgi exits after 500 requests by default, nothing would restart it
+        .env("PHP_FCGI_MAX_REQUESTS", "0")
+        // Worker processes on Unix; Windows builds serve one request at a time
+        .env("PHP_FCGI_CHILDREN", "4")
+        .env("PATH", platform::path_with(std::slice::from_ref(&php_dir)));
+    let mut child = process::runner()
+        .spawn(&cmd)
+        .map_err(|e| format!("Failed to start PHP {} FastCGI pool: {}", version, e))?;
+
+    // php-cgi reports startup problems and fatal errors on stderr
+    let log = logs::console_log(base_path, &pool_name(version));
+    if let Err(e) = logs::capture::capture(child.as_mut(), &log, policy) {
+        let _ = child.kill();
+        return Err(e);
+    }
+    tokio::spawn(watch_pool(base_path.to_path_buf(), version.to_string(), child.id()));
+    Ok(child)
+}
+
+// Notice a pool exiting on its own, which Apache would only answer with 503s
+async fn watch_pool(base_path: PathBuf, version: String, pid: u32) {
+    let exit = loop {
+        tokio::time::sleep(POOL_POLL).await;
+        let Ok(mut pools) = POOLS.lock() else { return };
+        // Stopped or replaced in the meantime
+        let Some(pool) = pools.get_mut(&version).filter(|pool| pool.child.id() == pid) else { return };
+        match pool.child.try_wait() {
+            Ok(None) => continue,
+            Ok(exit) => break exit,
+            Err(_) => break None,
+        }
+    };
+    pool_crashed(&base_path, &version, pid, exit).await;
+}
+
+// Report the crash the way service crashes are reported and start the pool
+// again after the supervisor's backoff, until it has crashed too often
+async fn pool_crashed(base_path: &Path, version: &str, pid: u32, exit: Option<Exit>) {
+    let name = pool_name(version);
+    let mut crash = ServiceCrash::new(&name, Some(pid));
+    crash.exit_code = exit.and_then(|exit| exit.code);
+    crash.signal = exit.and_then(|exit| exit.signal);
+    crash.last_lines = logs::last_lines_of(&logs::console_log(base_path, &name), supervisor::CRASH_LOG_LINES);
+
+    let delay = {
+        let Ok(mut pools) = POOLS.lock() else { return };
+        let Some(pool) = pools.get_mut(version) else { return };
+        let now = Instant::now();
+        pool.crashes.retain(|crashed| now.duration_since(*crashed) < supervisor::CRASH_WINDOW);
+        pool.crashes.push(now);
+        crash.crashes = pool.crashes.len();
+        crash.gave_up = crash.crashes >= supervisor::MAX_CRASHES;
+        if crash.gave_up {
+            pools.remove(version);
+            None
+        } else {
+            Some(supervisor::backoff(crash.crashes))
+        }
+    };
+    crash.restart_in_secs = delay.map(|delay| delay.as_secs());
+    jobs::emit_event(supervisor::CRASH_EVENT, crash);
+
+    let Some(delay) = delay else { return };
+    tokio::time::sleep(delay).await;
+    let policy = Policy::load(base_path);
+    let Ok(mut pools) = POOLS.lock() else { return };
+    // Stopped, or replaced by sync_pools, while waiting
+    let Some(pool) = pools.get_mut(version).filter(|pool| pool.child.id() == pid) else { return };
+    match spawn_pool(base_path, version, pool.port, policy) {
+        Ok(child) => pool.child = child,
+        Err(e) => {
+            println!("{}", e);
+            pools.remove(version);
+        }
+    }
+}
+
+// Bring the running pools in line with the settings: stop the ones no longer
+// needed (or whose port moved), start the missing ones. Killed pools are
+// reaped by the runtime once they have exited.
+pub(crate) fn sync_pools(base_path: &Path, settings: &Settings) -> Result<(), String> {
+    let wanted = pool_ports(base_path, settings);
+    let mut pools = POOLS.lock().map_err(|e| e.to_string())?;
+
+    pools.retain(|version, pool| {
+        let keep = wanted.contains(&(version.clone(), pool.port)) && matches!(pool.child.try_wait(), Ok(None));
+        if !keep {
+            let _ = pool.child.kill();
+        }
+        keep
+    });
+
+    let policy = Policy::from_settings(&settings.logs);
+    for (version, port) in wanted {
+        if let Entry::Vacant(entry) = pools.entry(version) {
+            let child = spawn_pool(base_path, entry.key(), port, policy)?;
+            entry.insert(Pool { port, child, crashes: Vec::new() });
+        }
+    }
+    Ok(())
+}
+
+// Start the pool of the version in `php_dir` again so it rereads its php.ini.
+// Versions without a pool are left alone.
+pub(crate) async fn restart_pool(base_path: &Path, php_dir: &Path) -> Result<(), String> {
+    // php/current resolves to the active version's directory
+    let php_dir = std::fs::canonicalize(php_dir).unwrap_or_else(|_| php_dir.to_path_buf());
+    let Some(version) = php_dir.file_name().and_then(|name| name.to_str()).map(str::to_string) else {
+        return Ok(());
+    };
+    // Out of the map, its watcher stands down instead of reporting a crash
+    let Some(mut pool) = POOLS.lock().map_err(|e| e.to_string())?.remove(&version) else {
+        return Ok(());
+    };
+
+    // The new pool needs the port the old one holds
+    let _ = pool.child.kill();
+    let _ = pool.child.wait().await;
+
+    let mut child = spawn_pool(base_path, &version, pool.port, Policy::load(base_path))?;
+    let mut pools = POOLS.lock().map_err(|e| e.to_string())?;
+    match pools.entry(version) {
+        Entry::Vacant(entry) => {
+            entry.insert(Pool { port: pool.port, child, crashes: Vec::new() });
+        }
+        // sync_pools started one in the meantime
+        Entry::Occupied(_) => {
+            let _ = child.kill();
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn stop_pools() {
+    if let Ok(mut pools) = POOLS.lock() {
+        for (_, mut pool) in pools.drain() {
+            let _ = pool.child.kill();
+        }
+    }
+}
+
+fn describe(base_path: &Path, settings: &Settings, project: &str) -> ProjectPhp {
+    let pin = settings.projects.get(project).map(|overrides| overrides.php_version.clone());
+    let (effective_version, fastcgi_port) = match &pin {
+        Some(pin) => {
+            let version = resolve_pin(base_path, pin);
+            let port = pool_ports(base_path, settings)
+                .into_iter()
+                .find(|(pooled, _)| Some(pooled) == version.as_ref())
+                .map(|(_, port)| port);
+            (version, port)
+        }
+        None => {
+            let current = std::fs::read_link(switch::current_dir(base_path)).ok();
+            let active = current
+                .and_then(|target| target.file_name().and_then(|name| name.to_str()).map(str::to_string))
+                .or_else(|| resolve_pin(base_path, &settings.php.active_version));
+            (active, None)
+        }
+    };
+
+    ProjectPhp {
+        project: project.to_string(),
+        path: settings.document_root(base_path).join(project).display().to_string(),
+        handler: if pin.is_some() { "fastcgi" } else { "module" }.to_string(),
+        php_version: pin,
+        effective_version,
+        fastcgi_port,
+    }
+}
+
+// Projects under the document root with the PHP version serving each
+#[tauri::command]
+pub async fn get_project_php_versions() -> Result<Vec<ProjectPhp>, String> {
+    let base_path = get_project_root()?;
+    let settings = settings::load(&base_path)?;
+
+    let mut projects: Vec<String> = std::fs::read_dir(settings.document_root(&base_path))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().to_str().map(str::to_string))
+        .filter(|name| !name.starts_with('.'))
+        .collect();
+    projects.sort();
+
+    Ok(projects
+        .iter()
+        .map(|project| describe(&base_path, &settings, project))
+        .collect())
+}
+
+// Pin `project` to `version`, or back to the global version when None
+#[tauri::command]
+pub async fn switch_project_php_version(project: String, version: Option<String>) -> Result<ProjectPhp, String> {
+    let base_path = get_project_root()?;
+    if !settings::is_project_name(&project) {
+        return Err(format!("'{}' is not a project directory name", project));
+    }
+
+    let previous = settings::load(&base_path)?;
+    if !previous.document_root(&base_path).join(&project).is_dir() {
+        return Err(format!("No project named {} in the document root", project));
+    }
+
+    let mut settings = previous.clone();
+    match version {
+        Some(version) => {
+            let installed = resolve_pin(&base_path, &version).ok_or_else(|| format!("PHP {} is not installed", version))?;
+            let php_dir = base_path.join("php").join(&installed);
+            if cgi_binary(&php_dir).is_none() {
+                return Err(format!("PHP {} has no php-cgi binary and cannot run as FastCGI", installed));
+            }
+            switch::prepare_php_ini(&base_path, &php_dir, &installed)?;
+            settings.projects.insert(project.clone(), ProjectSettings { php_version: version });
+        }
+        None => {
+            settings.projects.remove(&project);
+        }
+    }
+    settings.validate()?;
+    check_drop_in_include(&base_path)?;
+
+    write_projects_config(&base_path, &settings)?;
+    if let Err(e) = switch::apache_config_test(&base_path).await {
+        write_projects_config(&base_path, &previous)?;
+        return Err(format!("Apache rejected the project configuration, change rolled back: {}", e));
+    }
+    settings::save(&base_path, &settings)?;
+
+    // Pools only run alongside Apache; reloading it syncs them with the new
+    // routing, a stopped Apache picks it up on its next start
+    reload_if_running(find_service("apache")?).await?;
+    Ok(describe(&base_path, &settings, &project))
+}
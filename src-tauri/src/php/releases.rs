@@ -0,0 +1,210 @@
+// PHP release discovery. Reads an index in the windows.php.net releases.json
+// format, either from a URL or a local file, and keeps the last good copy in
+// php/.cache so the version list still works offline.
+
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use super::install;
+use crate::settings::PhpSettings;
+
+// A fetched index is reused for this long before asking the server again
+const CACHE_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Clone, Serialize)]
+pub struct PhpBuild {
+    pub thread_safe: bool,
+    // Visual Studio runtime the build needs ("vs16", "vs17")
+    pub compiler: String,
+    pub arch: String,
+    pub file: String,
+    pub url: String,
+    pub sha256: Option<String>,
+    pub size: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct PhpRelease {
+    // "8.3"
+    pub branch: String,
+    // Latest patch of the branch, "8.3.14"
+    pub version: String,
+    pub builds: Vec<PhpBuild>,
+}
+
+pub struct ReleaseIndex {
+    pub releases: Vec<PhpRelease>,
+    // The index could not be refreshed and an older cached copy was used
+    pub stale: bool,
+}
+
+// Architecture of the running DevStackBox, which is what PHP has to match
+pub fn host_arch() -> &'static str {
+    if cfg!(target_arch = "x86") {
+        "x86"
+    } else {
+        "x64"
+    }
+}
+
+fn parse_version(version: &str) -> Vec<u32> {
+    version.split('.').map(|part| part.parse().unwrap_or(0)).collect()
+}
+
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    parse_version(a).cmp(&parse_version(b))
+}
+
+// "8.3.14" -> "8.3"
+pub fn branch_of(version: &str) -> String {
+    version.split('.').take(2).collect::<Vec<_>>().join(".")
+}
+
+// "nts-vs16-x64" -> (false, "vs16", "x64")
+fn parse_build_key(key: &str) -> Option<(bool, String, String)> {
+    let mut parts = key.split('-');
+    let thread_safe = match parts.next()? {
+        "ts" => true,
+        "nts" => false,
+        _ => return None,
+    };
+    let compiler = parts.next().filter(|compiler| compiler.starts_with("vs") || compiler.starts_with("vc"))?;
+    let arch = parts.next().filter(|arch| matches!(*arch, "x64" | "x86"))?;
+    if parts.next().is_some() {
+        return None;
+    }
+    Some((thread_safe, compiler.to_string(), arch.to_string()))
+}
+
+// Archives are resolved against `mirror`, the directory releases.json lives in
+pub fn parse_index(json: &str, mirror: &str) -> Result<Vec<PhpRelease>, String> {
+    let index: serde_json::Value = serde_json::from_str(json).map_err(|e| format!("Invalid releases index: {}", e))?;
+    let branches = index.as_object().ok_or("Invalid releases index: expected an object of branches")?;
+
+    let mut releases = Vec::new();
+    for (branch, entry) in branches {
+        let version = match entry.get("version").and_then(|version| version.as_str()) {
+            Some(version) if install::validate_version(version).is_ok() => version.to_string(),
+            _ => continue,
+        };
+
+        let mut builds = Vec::new();
+        for (key, build) in entry.as_object().into_iter().flatten() {
+            let (thread_safe, compiler, arch) = match parse_build_key(key) {
+                Some(parsed) => parsed,
+                None => continue,
+            };
+            let zip = match build.get("zip") {
+                Some(zip) => zip,
+                None => continue,
+            };
+            let file = match zip.get("path").and_then(|path| path.as_str()) {
+                // Only plain file names, the index must not point us elsewhere
+                Some(file) if !file.contains(['/', '\\']) => file.to_string(),
+                _ => continue,
+            };
+            let text = |name: &str| zip.get(name).and_then(|value| value.as_str()).map(str::to_string);
+
+            builds.push(PhpBuild {
+                thread_safe,
+                compiler,
+                arch,
+                url: format!("{}/{}", mirror.trim_end_matches('/'), file),
+                sha256: text("sha256"),
+                size: text("size"),
+                file,
+            });
+        }
+
+        releases.push(PhpRelease { branch: branch.clone(), version, builds });
+    }
+
+    releases.sort_by(|a, b| compare_versions(&b.version, &a.version));
+    Ok(releases)
+}
+
+fn cache_path(base_path: &Path) -> PathBuf {
+    base_path.join("php").join(".cache").join("releases.json")
+}
+
+fn is_remote(source: &str) -> bool {
+    source.starts_with("https://") || source.starts_with("http://")
+}
+
+async fn fetch(source: &str) -> Result<String, String> {
+    install::client()?
+        .get(source)
+        .timeout(Duration::from_secs(15))
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("Failed to fetch {}: {}", source, e))?
+        .text()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// The configured index: a local file is read as is, a remote one goes through
+// the cache. `refresh` skips a cache that is still fresh.
+pub async fn load_index(base_path: &Path, php: &PhpSettings, refresh: bool) -> Result<ReleaseIndex, String> {
+    let source = php.releases_index.as_str();
+    if !is_remote(source) {
+        let path = base_path.join(source);
+        let json = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        return Ok(ReleaseIndex { releases: parse_index(&json, &php.mirror)?, stale: false });
+    }
+
+    let cache = cache_path(base_path);
+    let cache_age = std::fs::metadata(&cache)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok());
+    if !refresh && cache_age.is_some_and(|age| age < CACHE_MAX_AGE) {
+        if let Ok(json) = std::fs::read_to_string(&cache) {
+            return Ok(ReleaseIndex { releases: parse_index(&json, &php.mirror)?, stale: false });
+        }
+    }
+
+    match fetch(source).await.and_then(|json| parse_index(&json, &php.mirror).map(|releases| (json, releases))) {
+        Ok((json, releases)) => {
+            if let Some(parent) = cache.parent() {
+                let _ = std::fs::create_dir_all(parent);
+            }
+            let _ = std::fs::write(&cache, json);
+            Ok(ReleaseIndex { releases, stale: false })
+        }
+        // Offline: fall back to whatever we fetched last time
+        Err(e) => match std::fs::read_to_string(&cache) {
+            Ok(json) => Ok(ReleaseIndex { releases: parse_index(&json, &php.mirror)?, stale: true }),
+            Err(_) => Err(e),
+        },
+    }
+}
+
+// "8.3" picks the branch's latest patch, "8.3.14" must match exactly
+pub fn find_release<'a>(releases: &'a [PhpRelease], version: &str) -> Option<&'a PhpRelease> {
+    releases
+        .iter()
+        .find(|release| release.version == version || release.branch == version)
+}
+
+// The build matching the requested flavour, preferring the newest compiler
+pub fn select_build<'a>(release: &'a PhpRelease, thread_safe: bool, arch: &str) -> Option<&'a PhpBuild> {
+    release
+        .builds
+        .iter()
+        .filter(|build| build.thread_safe == thread_safe && build.arch == arch)
+        .max_by(|a, b| a.compiler.cmp(&b.compiler))
+}
+
+// Newest patch of the same branch, when it is newer than `installed`
+pub fn newer_patch(releases: &[PhpRelease], installed: &str) -> Option<String> {
+    let branch = branch_of(installed);
+    releases
+        .iter()
+        .find(|release| release.branch == branch)
+        .filter(|release| compare_versions(&release.version, installed) == Ordering::Greater)
+        .map(|release| release.version.clone())
+}
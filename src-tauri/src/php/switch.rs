@@ -0,0 +1,384 @@
+// Switching the active PHP version. php/current is swapped to the new version
+// in one step, then httpd.conf and the version's php.ini are rewired. When the
+// result fails Apache's config test everything is put back as it was.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use super::ini;
+use crate::process::{self, Cmd};
+use crate::services::{config, find_service, rewrite_lines};
+use crate::{backups, logs, php_binary, platform};
+
+// Apache module file names, PHP 8 and 7 on Windows, then Unix builds
+const APACHE_MODULES: &[&str] = &["php8apache2_4.dll", "php7apache2_4.dll", "libphp.so", "libphp8.so", "libphp7.so"];
+
+// httpd -t parses the config and loads the modules, nothing more
+const CONFIG_TEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub(crate) fn current_dir(base_path: &Path) -> PathBuf {
+    base_path.join("php").join("current")
+}
+
+// The Apache module shipped with a PHP build; NTS builds do not have one
+fn apache_module(php_dir: &Path) -> Option<&'static str> {
+    APACHE_MODULES
+        .iter()
+        .find(|module| php_dir.join(module).exists() || php_dir.join("lib").join(module).exists())
+        .copied()
+}
+
+fn module_path(base_path: &Path, php_dir: &Path, module: &str) -> PathBuf {
+    // Always through php/current, so switching between builds of the same
+    // major version leaves httpd.conf unchanged
+    let current = current_dir(base_path);
+    if php_dir.join(module).exists() {
+        current.join(module)
+    } else {
+        current.join("lib").join(module)
+    }
+}
+
+// The LoadModule line for a build, commented out when it has no Apache module
+fn load_module_line(base_path: &Path, php_dir: &Path) -> String {
+    match apache_module(php_dir) {
+        Some(module) => format!(
+            "LoadModule php_module \"{}\"",
+            platform::config_path(&module_path(base_path, php_dir, module))
+        ),
+        None => "# LoadModule php_module: the active PHP build has no Apache module".to_string(),
+    }
+}
+
+fn ini_dir_line(base_path: &Path) -> String {
+    let current = current_dir(base_path);
+    let ini_dir = ini::php_ini_path(&current).parent().map(Path::to_path_buf).unwrap_or(current);
+    format!("PHPIniDir \"{}\"", platform::config_path(&ini_dir))
+}
+
+// The mod_php section for httpd.conf. Everything but LoadModule sits in an
+// IfModule so the config stays valid when the module is not loaded.
+pub(crate) fn apache_php_block(base_path: &Path, php_dir: &Path) -> String {
+    format!(
+        "{}\n<IfModule php_module>\n    {}\n    <FilesMatch \"\\.php$\">\n        SetHandler application/x-httpd-php\n    </FilesMatch>\n</IfModule>",
+        load_module_line(base_path, php_dir),
+        ini_dir_line(base_path)
+    )
+}
+
+fn is_module_line(line: &str) -> bool {
+    let line = line.trim_start().trim_start_matches('#').trim_start();
+    line.starts_with("LoadModule php_module") || line.starts_with("LoadModule php7_module")
+}
+
+// Directives of the PHP-as-CGI setup the original httpd.conf shipped with:
+// /php/ aliased to a version directory and .php mapped to php-cgi through it
+fn is_cgi_directive(line: &str) -> bool {
+    let line = line.trim_start();
+    line.starts_with("ScriptAlias /php/") || line.starts_with("Action php-script") || line.starts_with("AddHandler php-script")
+}
+
+// Where the section opened by `lines[0]` closes, if it belongs to the CGI
+// setup: a <Files> that only maps to php-script, or the ExecCGI directory
+// php-cgi is served from
+fn cgi_section_end(lines: &[&str]) -> Option<usize> {
+    let open = lines[0].trim();
+    let (tag, close) = if open.starts_with("<Files ") {
+        ("Files", "</Files>")
+    } else if open.starts_with("<Directory ") {
+        ("Directory", "</Directory>")
+    } else {
+        return None;
+    };
+    let end = lines.iter().position(|line| line.trim() == close)?;
+    let mut body = lines[1..end].iter().map(|line| line.trim()).filter(|line| !line.is_empty() && !line.starts_with('#'));
+    let cgi = match tag {
+        "Files" => {
+            let body: Vec<&str> = body.collect();
+            !body.is_empty() && body.iter().all(|line| *line == "SetHandler php-script")
+        }
+        _ => open.contains("/php/") && body.any(|line| line.contains("ExecCGI")),
+    };
+    cgi.then_some(end)
+}
+
+// Bring the CGI setup in line with the new build. When mod_php is loaded the
+// CGI part is removed so a single handler serves .php; builds without an
+// Apache module keep CGI, pointed at php/current like the module is.
+fn rewire_cgi(content: &str, base_path: &Path, php_dir: &Path) -> String {
+    let current = platform::config_path(&current_dir(base_path));
+    let keep = apache_module(php_dir).is_none();
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+    let mut output: Vec<String> = Vec::with_capacity(lines.len());
+
+    let mut index = 0;
+    while index < lines.len() {
+        let line = lines[index];
+        let body = line.trim_end_matches(['\r', '\n']);
+        let ending = &line[body.len()..];
+        let indent = &body[..body.len() - body.trim_start().len()];
+
+        let section_end = cgi_section_end(&lines[index..]).map(|end| index + end);
+        if !is_cgi_directive(body) && section_end.is_none() {
+            output.push(line.to_string());
+            index += 1;
+            continue;
+        }
+
+        if keep {
+            let trimmed = body.trim_start();
+            if trimmed.starts_with("ScriptAlias /php/") {
+                output.push(format!("{}ScriptAlias /php/ \"{}/\"{}", indent, current, ending));
+            } else if trimmed.starts_with("<Directory ") {
+                output.push(format!("{}<Directory \"{}\">{}", indent, current, ending));
+            } else {
+                output.push(line.to_string());
+            }
+            index += 1;
+            continue;
+        }
+
+        // Drop the directive or section along with the comment introducing it
+        if output.last().is_some_and(|previous| previous.trim_start().starts_with('#') && previous.contains("CGI")) {
+            output.pop();
+        }
+        index = section_end.unwrap_or(index) + 1;
+    }
+    output.concat()
+}
+
+// The shipped phpmyadmin.conf runs PHP through the CGI setup. Once that is
+// gone phpMyAdmin is served from the include directory's drop-in instead.
+fn rewire_phpmyadmin_include(content: &str, base_path: &Path) -> String {
+    let include_dir = match find_service("apache") {
+        Ok(apache) => platform::config_path(&config::include_dir(apache, base_path)),
+        Err(_) => return content.to_string(),
+    };
+    let includes_dir = content.lines().any(|line| line.contains(&include_dir));
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+    let mut replaced = includes_dir;
+    lines
+        .into_iter()
+        .filter_map(|line| {
+            let trimmed = line.trim_start();
+            let shipped = trimmed.starts_with("Include ") && trimmed.trim_end().ends_with("/config/phpmyadmin.conf\"");
+            if !shipped {
+                return Some(line.to_string());
+            }
+            if replaced {
+                return None;
+            }
+            replaced = true;
+            let ending = &line[line.trim_end_matches(['\r', '\n']).len()..];
+            Some(format!("IncludeOptional \"{}/*.conf\"{}", include_dir, ending))
+        })
+        .collect()
+}
+
+// Point httpd.conf at the new build: replace the LoadModule and PHPIniDir
+// lines, or add the whole section if PHP was never wired up. A CGI setup is
+// rewired or removed to match.
+fn rewire_httpd_conf(content: &str, base_path: &Path, php_dir: &Path) -> String {
+    let mut content = rewire_cgi(content, base_path, php_dir);
+    if apache_module(php_dir).is_some() {
+        content = rewire_phpmyadmin_include(&content, base_path);
+    }
+
+    let mentions_php = content.lines().any(|line| is_module_line(line) || line.trim_start().starts_with("PHPIniDir"));
+    if !mentions_php {
+        if !content.ends_with('\n') {
+            content.push('\n');
+        }
+        content.push_str(&format!("\n# PHP\n{}\n", apache_php_block(base_path, php_dir)));
+        return content;
+    }
+
+    let (load_module, ini_dir) = (load_module_line(base_path, php_dir), ini_dir_line(base_path));
+    rewrite_lines(&content, |line| {
+        let indent = &line[..line.len() - line.trim_start().len()];
+        if is_module_line(line) {
+            Some(format!("{}{}", indent, load_module))
+        } else if line.trim_start().starts_with("PHPIniDir") {
+            Some(format!("{}{}", indent, ini_dir))
+        } else {
+            None
+        }
+    })
+}
+
+// Make sure the version has a php.ini and that it loads extensions from its own ext/
+pub(crate) fn prepare_php_ini(base_path: &Path, php_dir: &Path, version: &str) -> Result<(), String> {
+    let php_ini = ini::php_ini_path(php_dir);
+    let content = match std::fs::read_to_string(&php_ini) {
+        Ok(content) => content,
+        Err(_) => std::fs::read_to_string(php_ini.with_file_name("php.ini-development")).unwrap_or_default(),
+    };
+
+    let ext_dir = platform::config_path(&ini::ext_dir(php_dir));
+    let mut updated = ini::set_directive(&content, "extension_dir", &format!("\"{}\"", ext_dir));
+    // Without error_log PHP errors end up mixed into Apache's error.log
+    if ini::get_directive(&updated, "error_log").is_none() {
+        let error_log = platform::config_path(&logs::php_error_log_default(base_path));
+        updated = ini::set_directive(&updated, "error_log", &format!("\"{}\"", error_log));
+    }
+
+    backups::write_config_file(base_path, &php_ini, &updated, &format!("Switched to PHP {}", version))
+}
+
+// PATH for processes that need the active PHP (Apache loading php8ts.dll and
+// extension dependencies, Composer, the PHP terminal)
+pub(crate) fn path_env(base_path: &Path) -> std::ffi::OsString {
+    let current = current_dir(base_path);
+    platform::path_with(&[current.clone(), current.join("bin")])
+}
+
+// `httpd -t` against the current config. Ok when Apache is not installed.
+pub(crate) async fn apache_config_test(base_path: &Path) -> Result<(), String> {
+    let apache = find_service("apache")?;
+    let binary = match apache.binary(base_path) {
+        Some(binary) => binary,
+        None => return Ok(()),
+    };
+
+    let cmd = Cmd::new(&binary)
+        .arg("-f")
+        .arg(apache.config_path(base_path))
+        .arg("-t")
+        .current_dir(base_path)
+        .env("PATH", path_env(base_path));
+    let output = process::runner()
+        .output(&cmd, CONFIG_TEST_TIMEOUT)
+        .await
+        .map_err(|e| format!("Failed to test Apache configuration: {}", e))?;
+    if output.success() {
+        Ok(())
+    } else {
+        Err(output.stderr.trim().to_string())
+    }
+}
+
+// Files the switch may change, with their content beforehand, and those it
+// may create
+struct Snapshot {
+    link_target: Option<PathBuf>,
+    files: Vec<(PathBuf, String)>,
+    missing: Vec<PathBuf>,
+}
+
+impl Snapshot {
+    fn take(base_path: &Path, files: Vec<PathBuf>) -> Snapshot {
+        let (existing, missing): (Vec<PathBuf>, Vec<PathBuf>) = files.into_iter().partition(|path| path.exists());
+        Snapshot {
+            link_target: std::fs::read_link(current_dir(base_path)).ok(),
+            files: existing
+                .into_iter()
+                .filter_map(|path| {
+                    let content = std::fs::read_to_string(&path).ok()?;
+                    Some((path, content))
+                })
+                .collect(),
+            missing,
+        }
+    }
+
+    async fn restore(&self, base_path: &Path, reason: &str) {
+        let link = current_dir(base_path);
+        let _ = match self.link_target.clone() {
+            Some(target) => process::blocking(move || platform::replace_dir_link(&target, &link)).await,
+            None => platform::remove_dir_link(&link),
+        };
+        for (path, content) in &self.files {
+            let _ = backups::write_config_file(base_path, path, content, reason);
+        }
+        // Created by the switch, e.g. the new version's php.ini
+        for path in &self.missing {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+pub(crate) async fn switch(base_path: &Path, version: &str) -> Result<(), String> {
+    super::install::validate_version(version)?;
+    let php_dir = base_path.join("php").join(version);
+    if php_binary(&php_dir).is_none() {
+        return Err(format!("PHP {} is not installed", version));
+    }
+
+    let httpd_conf = find_service("apache")?.config_path(base_path);
+    let snapshot = Snapshot::take(base_path, vec![httpd_conf.clone(), ini::php_ini_path(&php_dir)]);
+
+    let result = async {
+        let (target, link) = (php_dir.clone(), current_dir(base_path));
+        // mklink on Windows
+        process::blocking(move || platform::replace_dir_link(&target, &link))
+            .await
+            .map_err(|e| format!("Failed to switch PHP version: {}", e))?;
+        prepare_php_ini(base_path, &php_dir, version)?;
+
+        if let Ok(content) = std::fs::read_to_string(&httpd_conf) {
+            let updated = rewire_httpd_conf(&content, base_path, &php_dir);
+            backups::write_config_file(base_path, &httpd_conf, &updated, &format!("Switched to PHP {}", version))?;
+        }
+
+        apache_config_test(base_path)
+            .await
+            .map_err(|e| format!("Apache rejected the configuration for PHP {}, switch rolled back: {}", version, e))
+    }
+    .await;
+
+    if result.is_err() {
+        snapshot.restore(base_path, &format!("Rolled back switch to PHP {}", version)).await;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The httpd.conf earlier releases tracked in config/, running PHP as CGI
+    const SHIPPED: &str = include_str!("testdata/httpd-cgi.conf");
+
+    fn php_build(name: &str, module: Option<&str>) -> (PathBuf, PathBuf) {
+        let base_path = std::env::temp_dir().join(format!("devstackbox-switch-{}-{}", name, std::process::id()));
+        let php_dir = base_path.join("php").join("8.3.14");
+        std::fs::create_dir_all(&php_dir).unwrap();
+        if let Some(module) = module {
+            std::fs::write(php_dir.join(module), "").unwrap();
+        }
+        (base_path, php_dir)
+    }
+
+    #[test]
+    fn module_build_replaces_the_cgi_setup() {
+        let (base_path, php_dir) = php_build("module", Some("php8apache2_4.dll"));
+        let rewired = rewire_httpd_conf(SHIPPED, &base_path, &php_dir);
+
+        assert!(!rewired.lines().any(is_cgi_directive));
+        assert!(!rewired.contains("php-script"));
+        assert!(!rewired.contains("ExecCGI"));
+        assert!(!rewired.contains("CGI"));
+        assert!(rewired.contains("LoadModule php_module"));
+        assert!(rewired.contains("SetHandler application/x-httpd-php"));
+        // phpMyAdmin comes from the include directory once CGI is gone
+        assert!(!rewired.contains("config/phpmyadmin.conf"));
+        assert!(rewired.contains("/config/apache.d/*.conf"));
+        // The document root section itself stays
+        assert!(rewired.contains("DirectoryIndex index.php index.html index.htm"));
+        let _ = std::fs::remove_dir_all(&base_path);
+    }
+
+    #[test]
+    fn build_without_module_keeps_cgi_on_the_active_version() {
+        let (base_path, php_dir) = php_build("cgi", None);
+        let rewired = rewire_httpd_conf(SHIPPED, &base_path, &php_dir);
+        let current = platform::config_path(&current_dir(&base_path));
+
+        assert!(rewired.contains(&format!("ScriptAlias /php/ \"{}/\"", current)));
+        assert!(rewired.contains(&format!("<Directory \"{}\">", current)));
+        assert!(!rewired.contains("php/8.2"));
+        assert!(rewired.contains("Action php-script /php/php-cgi.exe"));
+        assert!(rewired.contains("# LoadModule php_module"));
+        let _ = std::fs::remove_dir_all(&base_path);
+    }
+}
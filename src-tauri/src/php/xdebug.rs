@@ -0,0 +1,319 @@
+// Xdebug setup. Xdebug does not ship with PHP; a build dropped into a
+// version's ext/ directory is used when it matches the PHP build (branch,
+// thread safety, architecture) and PHP actually loads it. Setup wires it into
+// php.ini with step-debugging defaults, after which the mode can be switched
+// between debug, profile and coverage.
+
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use super::extensions::{extension_name, parse_extension_line, with_extension};
+use super::{ini, projects, switch, version_dir};
+use crate::process::{self, Cmd};
+use crate::services::{find_service, reload_if_running};
+use crate::{backups, get_project_root, php_binary, platform};
+
+// Values xdebug.mode accepts besides "off"
+const MODES: &[&str] = &["develop", "debug", "profile", "coverage", "trace", "gcstats"];
+
+const START_WITH_REQUEST: &[&str] = &["yes", "no", "trigger", "default"];
+
+// Xdebug 3's default, what IDEs listen on out of the box
+const DEFAULT_CLIENT_PORT: u16 = 9003;
+
+// Each check is a PHP one-liner, a build that hangs on load is not usable
+const PHP_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Serialize)]
+pub struct XdebugStatus {
+    // A matching Xdebug build is in ext/
+    pub available: bool,
+    pub file: Option<String>,
+    pub xdebug_version: Option<String>,
+    // Loaded through php.ini
+    pub enabled: bool,
+    pub modes: Vec<String>,
+    pub client_port: Option<u16>,
+    pub start_with_request: Option<String>,
+    // Why the builds in ext/ (if any) cannot be used
+    pub problems: Vec<String>,
+}
+
+// An Xdebug build that loads into the PHP version
+struct XdebugBuild {
+    file: String,
+    version: String,
+}
+
+// Result of looking through ext/, with why the builds there (if any) do not fit
+struct BuildSearch {
+    build: Option<XdebugBuild>,
+    problems: Vec<String>,
+}
+
+struct PhpBuild {
+    branch: String,
+    thread_safe: bool,
+    x64: bool,
+}
+
+fn php_command(php_dir: &Path) -> Result<Cmd, String> {
+    let binary = php_binary(php_dir).ok_or("PHP binary not found")?;
+    // -n: no php.ini, so a broken extension line cannot get in the way
+    Ok(Cmd::new(binary).arg("-n").env("PATH", platform::path_with(&[php_dir.to_path_buf()])))
+}
+
+async fn php_build(php_dir: &Path) -> Result<PhpBuild, String> {
+    let cmd = php_command(php_dir)?
+        .arg("-r")
+        .arg("echo PHP_MAJOR_VERSION . '.' . PHP_MINOR_VERSION . ' ' . PHP_ZTS . ' ' . PHP_INT_SIZE;");
+    let output = process::runner()
+        .output(&cmd, PHP_TIMEOUT)
+        .await
+        .map_err(|e| format!("Failed to run PHP: {}", e))?;
+    let stdout = output.stdout;
+    match stdout.split_whitespace().collect::<Vec<_>>()[..] {
+        [branch, zts, int_size] => Ok(PhpBuild {
+            branch: branch.to_string(),
+            thread_safe: zts == "1",
+            x64: int_size == "8",
+        }),
+        _ => Err(format!("Unexpected output from PHP: {}", stdout.trim())),
+    }
+}
+
+// Windows builds carry what they were built for in the file name
+// ("php_xdebug-3.3.2-8.3-vs16-nts-x86_64.dll"); .so builds do not and are
+// left to the load check
+fn check_build_name(file: &str, php: &PhpBuild) -> Result<(), String> {
+    let Some(stem) = file.strip_suffix(".dll") else {
+        return Ok(());
+    };
+    let parts: Vec<&str> = stem.split('-').collect();
+    if parts.len() < 4 {
+        return Ok(());
+    }
+
+    let flavour = |thread_safe| if thread_safe { "thread-safe" } else { "non-thread-safe" };
+    let thread_safe = !parts.contains(&"nts");
+    let x64 = parts.last() == Some(&"x86_64");
+    if parts[2] != php.branch {
+        Err(format!("{} is built for PHP {}, not {}", file, parts[2], php.branch))
+    } else if thread_safe != php.thread_safe {
+        Err(format!("{} is {} but PHP is {}", file, flavour(thread_safe), flavour(php.thread_safe)))
+    } else if x64 != php.x64 {
+        Err(format!("{} is built for another architecture", file))
+    } else {
+        Ok(())
+    }
+}
+
+// Load `path` into PHP and read Xdebug's version off `php -v`
+// ("with Xdebug v3.3.2, Copyright (c) 2002-2024, by Derick Rethans")
+async fn load_check(php_dir: &Path, path: &Path) -> Result<String, String> {
+    let cmd = php_command(php_dir)?
+        .arg("-d")
+        .arg(format!("zend_extension={}", path.display()))
+        .arg("-v");
+    let output = process::runner()
+        .output(&cmd, PHP_TIMEOUT)
+        .await
+        .map_err(|e| format!("Failed to run PHP: {}", e))?;
+    let stdout = output.stdout;
+    let stderr = output.stderr;
+
+    if let Some(start) = stdout.find("with Xdebug v") {
+        let version = &stdout[start + "with Xdebug v".len()..];
+        let end = version.find([',', ' ', '\n']).unwrap_or(version.len());
+        return Ok(version[..end].to_string());
+    }
+
+    // PHP explains API and thread-safety mismatches on startup
+    let reason = stderr
+        .lines()
+        .chain(stdout.lines())
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with("PHP ") && !line.starts_with("Copyright") && !line.starts_with("Zend Engine"))
+        .or_else(|| stderr.lines().map(str::trim).find(|line| !line.is_empty()))
+        .unwrap_or("PHP did not load it");
+    Err(format!("{}: {}", path.file_name().and_then(|name| name.to_str()).unwrap_or_default(), reason))
+}
+
+// The first Xdebug build in ext/ that fits, newest first, with the reasons
+// the others were passed over
+async fn find_build(php_dir: &Path) -> Result<BuildSearch, String> {
+    let ext_dir = ini::ext_dir(php_dir);
+    let mut files: Vec<String> = std::fs::read_dir(&ext_dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| entry.file_name().to_str().map(str::to_string))
+        .filter(|file| (file.ends_with(".dll") || file.ends_with(".so")) && extension_name(file) == "xdebug")
+        .collect();
+    files.sort_by(|a, b| b.cmp(a));
+
+    let php = php_build(php_dir).await?;
+    if files.is_empty() {
+        let flavour = if php.thread_safe { "TS" } else { "NTS" };
+        let arch = if php.x64 { "64 bit" } else { "32 bit" };
+        let problem = format!(
+            "No Xdebug build in {}; get the PHP {} {} {} one from xdebug.org/download",
+            ext_dir.display(),
+            php.branch,
+            flavour,
+            arch
+        );
+        return Ok(BuildSearch { build: None, problems: vec![problem] });
+    }
+
+    let mut problems = Vec::new();
+    for file in files {
+        let checked = match check_build_name(&file, &php) {
+            Ok(()) => load_check(php_dir, &ext_dir.join(&file)).await,
+            Err(problem) => Err(problem),
+        };
+        match checked {
+            Ok(version) => return Ok(BuildSearch { build: Some(XdebugBuild { file, version }), problems }),
+            Err(problem) => problems.push(problem),
+        }
+    }
+    Ok(BuildSearch { build: None, problems })
+}
+
+fn is_xdebug_line(line: &str) -> bool {
+    parse_extension_line(line).is_some_and(|(name, _, zend)| zend && name == "xdebug")
+}
+
+// php.ini has an active zend_extension line for Xdebug
+fn is_loaded(content: &str) -> bool {
+    content
+        .lines()
+        .filter_map(parse_extension_line)
+        .any(|(name, active, zend)| active && zend && name == "xdebug")
+}
+
+async fn status(php_dir: &Path) -> Result<XdebugStatus, String> {
+    let content = std::fs::read_to_string(ini::php_ini_path(php_dir)).unwrap_or_default();
+    let modes = ini::get_directive(&content, "xdebug.mode")
+        .map(|modes| {
+            modes
+                .split(',')
+                .map(str::trim)
+                .filter(|mode| !mode.is_empty() && *mode != "off")
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let BuildSearch { build, problems } = find_build(php_dir).await?;
+    Ok(XdebugStatus {
+        available: build.is_some(),
+        file: build.as_ref().map(|build| build.file.clone()),
+        xdebug_version: build.map(|build| build.version),
+        enabled: is_loaded(&content),
+        modes,
+        client_port: ini::get_directive(&content, "xdebug.client_port").and_then(|port| port.parse().ok()),
+        start_with_request: ini::get_directive(&content, "xdebug.start_with_request"),
+        problems,
+    })
+}
+
+// xdebug.mode of the active version, None when Xdebug is not loaded
+pub(crate) fn active_mode(base_path: &Path) -> Option<String> {
+    let content = std::fs::read_to_string(ini::php_ini_path(&switch::current_dir(base_path))).ok()?;
+    if !is_loaded(&content) {
+        return None;
+    }
+    // Xdebug 3 defaults to "develop" when xdebug.mode is not set
+    Some(ini::get_directive(&content, "xdebug.mode").unwrap_or_else(|| "develop".to_string()))
+}
+
+fn output_dir(base_path: &Path) -> PathBuf {
+    base_path.join("tmp").join("xdebug")
+}
+
+async fn write_php_ini(base_path: &Path, php_ini: &Path, content: &str, reason: &str, php_dir: &Path) -> Result<XdebugStatus, String> {
+    backups::write_config_file(base_path, php_ini, content, reason)?;
+    reload_if_running(find_service("apache")?).await?;
+    projects::restart_pool(base_path, php_dir).await?;
+    status(php_dir).await
+}
+
+#[tauri::command]
+pub async fn get_xdebug_status(version: Option<String>) -> Result<XdebugStatus, String> {
+    let base_path = get_project_root()?;
+    status(&version_dir(&base_path, version.as_deref())?).await
+}
+
+// Load the matching Xdebug build and configure step debugging: mode "debug",
+// IDE on 127.0.0.1:`client_port` (9003), start_with_request "yes"
+#[tauri::command]
+pub async fn setup_xdebug(
+    version: Option<String>,
+    client_port: Option<u16>,
+    start_with_request: Option<String>,
+) -> Result<XdebugStatus, String> {
+    let base_path = get_project_root()?;
+    let php_dir = version_dir(&base_path, version.as_deref())?;
+
+    let start_with_request = start_with_request.unwrap_or_else(|| "yes".to_string());
+    if !START_WITH_REQUEST.contains(&start_with_request.as_str()) {
+        return Err(format!("start_with_request must be one of {}", START_WITH_REQUEST.join(", ")));
+    }
+    let client_port = client_port.unwrap_or(DEFAULT_CLIENT_PORT);
+    if client_port == 0 {
+        return Err("Xdebug client port must be between 1 and 65535".to_string());
+    }
+
+    let file = match find_build(&php_dir).await? {
+        BuildSearch { build: Some(build), .. } => build.file,
+        BuildSearch { problems, .. } => return Err(problems.join("; ")),
+    };
+
+    let php_ini = ini::ensure_php_ini(&base_path, &php_dir)?;
+    let content = std::fs::read_to_string(&php_ini).map_err(|e| format!("Failed to read {}: {}", php_ini.display(), e))?;
+
+    // Drop lines for other Xdebug builds, then load this one
+    let content: String = content.split_inclusive('\n').filter(|line| !is_xdebug_line(line)).collect();
+    let mut content = with_extension(&content, "xdebug", &file, true, true);
+
+    let output_dir = output_dir(&base_path);
+    std::fs::create_dir_all(&output_dir).map_err(|e| e.to_string())?;
+    for (key, value) in [
+        ("xdebug.mode", "debug".to_string()),
+        ("xdebug.client_host", "127.0.0.1".to_string()),
+        ("xdebug.client_port", client_port.to_string()),
+        ("xdebug.start_with_request", start_with_request),
+        ("xdebug.output_dir", format!("\"{}\"", platform::config_path(&output_dir))),
+    ] {
+        content = ini::set_directive(&content, key, &value);
+    }
+
+    write_php_ini(&base_path, &php_ini, &content, "Set up Xdebug", &php_dir).await
+}
+
+// Switch Xdebug between modes ("debug", "profile", "coverage", ...). An empty
+// list turns Xdebug off and stops loading it.
+#[tauri::command]
+pub async fn set_xdebug_mode(version: Option<String>, modes: Vec<String>) -> Result<XdebugStatus, String> {
+    let base_path = get_project_root()?;
+    let php_dir = version_dir(&base_path, version.as_deref())?;
+    if let Some(unknown) = modes.iter().find(|mode| !MODES.contains(&mode.as_str())) {
+        return Err(format!("Unknown Xdebug mode '{}', expected one of {}", unknown, MODES.join(", ")));
+    }
+
+    let php_ini = ini::php_ini_path(&php_dir);
+    let content = std::fs::read_to_string(&php_ini).map_err(|e| format!("Failed to read {}: {}", php_ini.display(), e))?;
+    if !content.lines().any(is_xdebug_line) {
+        return Err("Xdebug is not set up for this PHP version, run setup_xdebug first".to_string());
+    }
+
+    let enable = !modes.is_empty();
+    let mode = if enable { modes.join(",") } else { "off".to_string() };
+    // The file name only matters when a line has to be added, and one exists
+    let content = with_extension(&content, "xdebug", "xdebug", true, enable);
+    let content = ini::set_directive(&content, "xdebug.mode", &mode);
+
+    write_php_ini(&base_path, &php_ini, &content, &format!("Set Xdebug mode to {}", mode), &php_dir).await
+}
@@ -0,0 +1,543 @@
+// Platform layer: executable names, process control, port checks and directory
+// links. Everything OS-specific lives here so the Tauri commands in lib.rs work
+// the same on Windows, Linux and macOS.
+
+use std::path::{Path, PathBuf};
+#[cfg(windows)]
+use std::process::Command;
+
+// Append the platform executable suffix ("mysqld" -> "mysqld.exe" on Windows)
+pub fn exe_name(name: &str) -> String {
+    format!("{}{}", name, std::env::consts::EXE_SUFFIX)
+}
+
+// Locate an executable inside a bundled directory. Windows archives keep the
+// binaries at the top level (php/8.2/php.exe) while Linux/macOS builds put them
+// under bin/ (php/8.2/bin/php), so both layouts are checked.
+pub fn find_executable(dir: &Path, name: &str) -> Option<PathBuf> {
+    let file_name = exe_name(name);
+    [dir.join(&file_name), dir.join("bin").join(&file_name)]
+        .into_iter()
+        .find(|path| path.is_file())
+}
+
+// Search PATH (and the sbin directories that are often missing from it for
+// non-root users) for the first of the given executable names
+pub fn find_in_path(names: &[&str]) -> Option<PathBuf> {
+    let mut dirs: Vec<PathBuf> = std::env::var_os("PATH")
+        .map(|paths| std::env::split_paths(&paths).collect())
+        .unwrap_or_default();
+    if cfg!(unix) {
+        for extra in ["/usr/sbin", "/usr/local/sbin", "/opt/homebrew/bin", "/opt/homebrew/sbin"] {
+            dirs.push(PathBuf::from(extra));
+        }
+    }
+
+    names.iter().find_map(|name| {
+        let file_name = exe_name(name);
+        dirs.iter().map(|dir| dir.join(&file_name)).find(|path| path.is_file())
+    })
+}
+
+// Resolve a service binary: the bundled copy under `bundled_dir` wins, then
+// the system installation (e.g. Debian ships Apache as /usr/sbin/apache2).
+pub fn resolve_binary(bundled_dir: &Path, names: &[&str]) -> Option<PathBuf> {
+    names
+        .iter()
+        .find_map(|name| find_executable(bundled_dir, name))
+        .or_else(|| find_in_path(names))
+}
+
+// Apache needs a ServerRoot containing modules/ plus a mime.types file. The
+// bundled build has both under apache/, system packages spread them around.
+pub fn apache_layout(apache_dir: &Path, binary: &Path) -> (PathBuf, PathBuf) {
+    if binary.starts_with(apache_dir) || cfg!(windows) {
+        return (apache_dir.to_path_buf(), apache_dir.join("conf").join("mime.types"));
+    }
+
+    let server_root = [
+        "/usr/lib/apache2",
+        "/usr/lib64/httpd",
+        "/usr/lib/httpd",
+        "/usr/local/opt/httpd/lib/httpd",
+        "/opt/homebrew/opt/httpd/lib/httpd",
+    ]
+    .iter()
+    .map(PathBuf::from)
+    .find(|root| root.join("modules").is_dir())
+    .unwrap_or_else(|| apache_dir.to_path_buf());
+
+    let types_config = [
+        PathBuf::from("/etc/mime.types"),
+        PathBuf::from("/etc/apache2/mime.types"),
+        PathBuf::from("/usr/local/etc/httpd/mime.types"),
+        PathBuf::from("/opt/homebrew/etc/httpd/mime.types"),
+    ]
+    .into_iter()
+    .find(|path| path.is_file())
+    .unwrap_or_else(|| apache_dir.join("conf").join("mime.types"));
+
+    (server_root, types_config)
+}
+
+// Modules loaded ahead of the essential ones. mod_log_config is shared on
+// every platform (Apache Lounge ships it as a module too); the MPM and unixd
+// are compiled into the Windows build. Guarded so distributions that link
+// them statically don't warn.
+pub fn apache_platform_modules() -> &'static str {
+    if cfg!(windows) {
+        r#"<IfModule !log_config_module>
+LoadModule log_config_module modules/mod_log_config.so
+</IfModule>
+"#
+    } else {
+        r#"<IfModule !mpm_event_module>
+LoadModule mpm_event_module modules/mod_mpm_event.so
+</IfModule>
+<IfModule !unixd_module>
+LoadModule unixd_module modules/mod_unixd.so
+</IfModule>
+<IfModule !log_config_module>
+LoadModule log_config_module modules/mod_log_config.so
+</IfModule>
+"#
+    }
+}
+
+// Render a path the way Apache and MySQL config files expect it (forward slashes)
+pub fn config_path(path: &Path) -> String {
+    path.display().to_string().replace('\\', "/")
+}
+
+// PIDs of the processes listening on a local TCP port. None means ownership
+// could not be determined on this system; an empty list means the port is
+// held by processes we are not allowed to inspect.
+#[cfg(target_os = "linux")]
+pub fn listening_pids(port: u16) -> Option<Vec<u32>> {
+    // /proc/net/tcp{,6}: "sl local_address rem_address st ... inode"
+    let mut inodes = Vec::new();
+    for table in ["/proc/net/tcp", "/proc/net/tcp6"] {
+        let Ok(content) = std::fs::read_to_string(table) else { continue };
+        for line in content.lines().skip(1) {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 10 || fields[3] != "0A" {
+                continue;
+            }
+            let local_port = fields[1].rsplit(':').next().and_then(|hex| u16::from_str_radix(hex, 16).ok());
+            if local_port == Some(port) {
+                inodes.push(format!("socket:[{}]", fields[9]));
+            }
+        }
+    }
+    if inodes.is_empty() {
+        return Some(Vec::new());
+    }
+
+    let mut pids = Vec::new();
+    for entry in std::fs::read_dir("/proc").ok()?.flatten() {
+        let Some(pid) = entry.file_name().to_str().and_then(|name| name.parse::<u32>().ok()) else { continue };
+        let Ok(fds) = std::fs::read_dir(entry.path().join("fd")) else { continue };
+        let owns_socket = fds.flatten().any(|fd| {
+            std::fs::read_link(fd.path())
+                .map(|target| inodes.iter().any(|inode| target.as_os_str() == inode.as_str()))
+                .unwrap_or(false)
+        });
+        if owns_socket {
+            pids.push(pid);
+        }
+    }
+    Some(pids)
+}
+
+#[cfg(target_os = "macos")]
+pub fn listening_pids(port: u16) -> Option<Vec<u32>> {
+    let output = std::process::Command::new("lsof")
+        .args(["-nP", &format!("-iTCP:{}", port), "-sTCP:LISTEN", "-t"])
+        .output()
+        .ok()?;
+    Some(
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| line.trim().parse().ok())
+            .collect(),
+    )
+}
+
+#[cfg(windows)]
+pub fn listening_pids(port: u16) -> Option<Vec<u32>> {
+    // "  TCP    0.0.0.0:3306    0.0.0.0:0    LISTENING    1234"
+    let output = Command::new("netstat").arg("-ano").output().ok()?;
+    let suffix = format!(":{}", port);
+    let mut pids = Vec::new();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() == 5 && fields[0].starts_with("TCP") && fields[1].ends_with(&suffix) && fields[3] == "LISTENING" {
+            if let Ok(pid) = fields[4].parse::<u32>() {
+                if !pids.contains(&pid) {
+                    pids.push(pid);
+                }
+            }
+        }
+    }
+    Some(pids)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+pub fn listening_pids(_port: u16) -> Option<Vec<u32>> {
+    None
+}
+
+// Parent PID of a process, used to accept sockets held by worker children
+#[cfg(target_os = "linux")]
+pub fn parent_pid(pid: u32) -> Option<u32> {
+    // /proc/<pid>/stat: "pid (comm) state ppid ...", comm may contain spaces
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    let after_comm = &stat[stat.rfind(')')? + 1..];
+    after_comm.split_whitespace().nth(1)?.parse().ok()
+}
+
+#[cfg(target_os = "macos")]
+pub fn parent_pid(pid: u32) -> Option<u32> {
+    let output = std::process::Command::new("ps")
+        .args(["-o", "ppid=", "-p", &pid.to_string()])
+        .output()
+        .ok()?;
+    String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+}
+
+#[cfg(windows)]
+pub fn parent_pid(pid: u32) -> Option<u32> {
+    let output = Command::new("powershell")
+        .args([
+            "-NoProfile",
+            "-Command",
+            &format!("(Get-CimInstance Win32_Process -Filter \"ProcessId={}\").ParentProcessId", pid),
+        ])
+        .output()
+        .ok()?;
+    String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+pub fn parent_pid(_pid: u32) -> Option<u32> {
+    None
+}
+
+// Executable name of a process, for "port 80 is used by Skype.exe" messages
+#[cfg(target_os = "linux")]
+pub fn process_name(pid: u32) -> Option<String> {
+    let name = std::fs::read_to_string(format!("/proc/{}/comm", pid)).ok()?;
+    Some(name.trim().to_string()).filter(|name| !name.is_empty())
+}
+
+#[cfg(target_os = "macos")]
+pub fn process_name(pid: u32) -> Option<String> {
+    let output = std::process::Command::new("ps")
+        .args(["-o", "comm=", "-p", &pid.to_string()])
+        .output()
+        .ok()?;
+    let name = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    // ps prints the full path of the executable
+    let name = name.rsplit('/').next().unwrap_or_default().to_string();
+    Some(name).filter(|name| !name.is_empty())
+}
+
+#[cfg(windows)]
+pub fn process_name(pid: u32) -> Option<String> {
+    // Output looks like: "Skype.exe","1234","Console","1","120,000 K"
+    let output = Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {}", pid), "/NH", "/FO", "CSV"])
+        .output()
+        .ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let first_field = stdout.lines().next()?.split(',').next()?.trim_matches('"').to_string();
+    Some(first_field).filter(|name| !name.is_empty() && !name.starts_with("INFO:"))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+pub fn process_name(_pid: u32) -> Option<String> {
+    None
+}
+
+// Full path of a process's executable, to tell our servers from other
+// programs that happen to reuse a PID. None when it cannot be inspected.
+#[cfg(target_os = "linux")]
+pub fn process_path(pid: u32) -> Option<PathBuf> {
+    // A replaced binary shows up as "/path/mysqld (deleted)"
+    let target = std::fs::read_link(format!("/proc/{}/exe", pid)).ok()?;
+    let target = target.to_string_lossy();
+    Some(PathBuf::from(target.strip_suffix(" (deleted)").unwrap_or(&target)))
+}
+
+#[cfg(target_os = "macos")]
+pub fn process_path(pid: u32) -> Option<PathBuf> {
+    let output = std::process::Command::new("ps")
+        .args(["-o", "comm=", "-p", &pid.to_string()])
+        .output()
+        .ok()?;
+    let path = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    Some(PathBuf::from(path)).filter(|path| path.is_absolute())
+}
+
+#[cfg(windows)]
+pub fn process_path(pid: u32) -> Option<PathBuf> {
+    let output = Command::new("powershell")
+        .args([
+            "-NoProfile",
+            "-Command",
+            &format!("(Get-CimInstance Win32_Process -Filter \"ProcessId={}\").ExecutablePath", pid),
+        ])
+        .output()
+        .ok()?;
+    let path = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    Some(PathBuf::from(path)).filter(|path| !path.as_os_str().is_empty())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+pub fn process_path(_pid: u32) -> Option<PathBuf> {
+    None
+}
+
+// Whether a process with this PID exists, whoever owns it
+#[cfg(unix)]
+pub fn process_alive(pid: u32) -> bool {
+    // kill(0, ...) would address our own process group
+    if pid == 0 {
+        return false;
+    }
+    let result = unsafe { libc::kill(pid as libc::pid_t, 0) };
+    let exists = result == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM);
+    exists && !is_zombie(pid)
+}
+
+// Exited but not yet reaped by its parent
+#[cfg(target_os = "linux")]
+fn is_zombie(pid: u32) -> bool {
+    std::fs::read_to_string(format!("/proc/{}/stat", pid))
+        .ok()
+        .and_then(|stat| Some(stat[stat.rfind(')')? + 1..].split_whitespace().next()? == "Z"))
+        .unwrap_or(false)
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn is_zombie(_pid: u32) -> bool {
+    false
+}
+
+#[cfg(windows)]
+pub fn process_alive(pid: u32) -> bool {
+    process_name(pid).is_some()
+}
+
+// Ask a process to exit: SIGTERM on Unix, a close request from taskkill on
+// Windows. Servers without a window may ignore the latter.
+#[cfg(unix)]
+pub fn stop_process(pid: u32) -> Result<(), String> {
+    send_signal(pid, libc::SIGTERM)
+}
+
+#[cfg(windows)]
+pub fn stop_process(pid: u32) -> Result<(), String> {
+    taskkill(&["/T", "/PID", &pid.to_string()])
+}
+
+// End a process and its children at once, for servers that did not stop when asked
+#[cfg(unix)]
+pub fn kill_process(pid: u32) -> Result<(), String> {
+    send_signal(pid, libc::SIGKILL)
+}
+
+#[cfg(windows)]
+pub fn kill_process(pid: u32) -> Result<(), String> {
+    taskkill(&["/F", "/T", "/PID", &pid.to_string()])
+}
+
+#[cfg(windows)]
+fn taskkill(args: &[&str]) -> Result<(), String> {
+    let output = Command::new("taskkill")
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run taskkill: {}", e))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+// Signals that make a running server reopen its log files
+#[derive(Clone, Copy)]
+pub enum ReopenSignal {
+    // Apache's graceful restart (SIGUSR1)
+    Graceful,
+    // mysqld flushes its logs (SIGHUP)
+    Hangup,
+}
+
+// Windows has no such signals; logs of running servers are copied and
+// truncated in place there instead
+pub const CAN_REOPEN_LOGS: bool = cfg!(unix);
+
+#[cfg(unix)]
+pub fn reopen_logs(pid: u32, signal: ReopenSignal) -> Result<(), String> {
+    send_signal(pid, match signal {
+        ReopenSignal::Graceful => libc::SIGUSR1,
+        ReopenSignal::Hangup => libc::SIGHUP,
+    })
+}
+
+#[cfg(windows)]
+pub fn reopen_logs(_pid: u32, _signal: ReopenSignal) -> Result<(), String> {
+    Err("Servers cannot be signalled to reopen their logs on Windows".to_string())
+}
+
+#[cfg(unix)]
+fn send_signal(pid: u32, signal: libc::c_int) -> Result<(), String> {
+    let result = unsafe { libc::kill(pid as libc::pid_t, signal) };
+    if result == 0 {
+        return Ok(());
+    }
+    let error = std::io::Error::last_os_error();
+    if error.raw_os_error() == Some(libc::ESRCH) {
+        // Already gone, which is what the caller wanted
+        Ok(())
+    } else {
+        Err(format!("Failed to signal process {}: {}", pid, error))
+    }
+}
+
+// Signal that ended a process, None when it exited on its own
+#[cfg(unix)]
+pub fn exit_signal(status: &std::process::ExitStatus) -> Option<i32> {
+    use std::os::unix::process::ExitStatusExt;
+    status.signal()
+}
+
+#[cfg(windows)]
+pub fn exit_signal(_status: &std::process::ExitStatus) -> Option<i32> {
+    None
+}
+
+// PATH with `dirs` in front, for child processes that need bundled tools
+pub fn path_with(dirs: &[PathBuf]) -> std::ffi::OsString {
+    let existing = std::env::var_os("PATH").unwrap_or_default();
+    let paths = dirs.iter().cloned().chain(std::env::split_paths(&existing));
+    std::env::join_paths(paths).unwrap_or(existing)
+}
+
+// Point `link` at `target` (a symlink on Unix, a directory junction on Windows)
+#[cfg(unix)]
+pub fn create_dir_link(target: &Path, link: &Path) -> Result<(), String> {
+    std::os::unix::fs::symlink(target, link)
+        .map_err(|e| format!("Failed to link {} -> {}: {}", link.display(), target.display(), e))
+}
+
+#[cfg(windows)]
+pub fn create_dir_link(target: &Path, link: &Path) -> Result<(), String> {
+    // mklink is a cmd.exe builtin, not a standalone executable
+    let output = Command::new("cmd")
+        .arg("/C")
+        .arg("mklink")
+        .arg("/J")
+        .arg(link)
+        .arg(target)
+        .output()
+        .map_err(|e| format!("Failed to run mklink: {}", e))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "Failed to link {} -> {}: {}",
+            link.display(),
+            target.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+// Remove a link created by `create_dir_link` without touching its target.
+// A plain directory (e.g. a copied install) is removed recursively.
+pub fn remove_dir_link(link: &Path) -> Result<(), String> {
+    let metadata = match std::fs::symlink_metadata(link) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e.to_string()),
+    };
+
+    let result = if metadata.file_type().is_symlink() {
+        if cfg!(windows) {
+            std::fs::remove_dir(link)
+        } else {
+            std::fs::remove_file(link)
+        }
+    } else if is_junction(&metadata) {
+        std::fs::remove_dir(link)
+    } else if metadata.is_dir() {
+        std::fs::remove_dir_all(link)
+    } else {
+        std::fs::remove_file(link)
+    };
+    result.map_err(|e| format!("Failed to remove {}: {}", link.display(), e))
+}
+
+// Point `link` at `target` without ever leaving it missing on failure: the
+// new link is created alongside and only then swapped in for the old one
+pub fn replace_dir_link(target: &Path, link: &Path) -> Result<(), String> {
+    let name = link.file_name().and_then(|name| name.to_str()).unwrap_or("link");
+    let staged = link.with_file_name(format!(".{}.new", name));
+    remove_dir_link(&staged)?;
+    create_dir_link(target, &staged)?;
+
+    // Renaming over a symlink replaces it atomically on Unix
+    let old = std::fs::symlink_metadata(link).ok();
+    if cfg!(unix) && old.as_ref().is_none_or(|metadata| metadata.file_type().is_symlink()) {
+        return std::fs::rename(&staged, link).map_err(|e| {
+            let _ = remove_dir_link(&staged);
+            format!("Failed to replace {}: {}", link.display(), e)
+        });
+    }
+
+    // Junctions and directories cannot be renamed over, move the old one aside first
+    let retired = link.with_file_name(format!(".{}.old", name));
+    if old.is_some() {
+        remove_dir_link(&retired)?;
+        if let Err(e) = std::fs::rename(link, &retired) {
+            let _ = remove_dir_link(&staged);
+            return Err(format!("Failed to replace {}: {}", link.display(), e));
+        }
+    }
+    if let Err(e) = std::fs::rename(&staged, link) {
+        if old.is_some() {
+            let _ = std::fs::rename(&retired, link);
+        }
+        let _ = remove_dir_link(&staged);
+        return Err(format!("Failed to replace {}: {}", link.display(), e));
+    }
+    remove_dir_link(&retired)
+}
+
+#[cfg(windows)]
+fn is_junction(metadata: &std::fs::Metadata) -> bool {
+    use std::os::windows::fs::MetadataExt;
+    const FILE_ATTRIBUTE_REPARSE_POINT: u32 = 0x400;
+    metadata.file_attributes() & FILE_ATTRIBUTE_REPARSE_POINT != 0
+}
+
+#[cfg(unix)]
+fn is_junction(_metadata: &std::fs::Metadata) -> bool {
+    false
+}
+
+// Identity of the file behind `metadata`, to notice a log path now naming a
+// new file after rotation. Windows has no stable equivalent; callers fall back
+// to watching the size.
+#[cfg(unix)]
+pub fn file_id(metadata: &std::fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(windows)]
+pub fn file_id(_metadata: &std::fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
@@ -0,0 +1,188 @@
+// Fake process runner for tests. Commands are answered from canned responses
+// (the first whose matcher accepts the command, success with no output when
+// none does) and recorded for inspection. Spawned servers get PIDs no real
+// process can have, unless set_server_pid says otherwise, and run until killed
+// or ended through `exit`.
+
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::watch;
+
+use super::{BoxFuture, Cmd, Exit, Output, OutputStream, Process, ProcessRunner};
+
+// Above any Linux pid_max, so platform checks never find them alive
+const FIRST_PID: u32 = 1_000_000_000;
+
+const SIGKILL: i32 = 9;
+
+type Matcher = Box<dyn Fn(&Cmd) -> bool + Send + Sync>;
+
+struct Response {
+    matches: Matcher,
+    // How long the command takes to finish
+    delay: Duration,
+    result: Result<Output, String>,
+}
+
+pub(crate) struct FakeRunner {
+    responses: Mutex<Vec<Response>>,
+    calls: Mutex<Vec<Cmd>>,
+    // Console output every spawned server prints
+    server_output: Mutex<Vec<u8>>,
+    servers: Mutex<HashMap<u32, watch::Sender<Option<Exit>>>>,
+    // PID every spawned server reports instead of a fresh one
+    server_pid: Mutex<Option<u32>>,
+    next_pid: AtomicU32,
+}
+
+impl Cmd {
+    // Whether any argument is `arg`
+    pub(crate) fn has_arg(&self, arg: &str) -> bool {
+        self.args.iter().any(|existing| existing == arg)
+    }
+}
+
+impl Output {
+    pub(crate) fn ok(stdout: &str) -> Output {
+        Output { code: Some(0), stdout: stdout.to_string(), stderr: String::new() }
+    }
+
+    pub(crate) fn failed(code: i32, stderr: &str) -> Output {
+        Output { code: Some(code), stdout: String::new(), stderr: stderr.to_string() }
+    }
+}
+
+impl FakeRunner {
+    pub(crate) fn new() -> FakeRunner {
+        FakeRunner {
+            responses: Mutex::new(Vec::new()),
+            calls: Mutex::new(Vec::new()),
+            server_output: Mutex::new(Vec::new()),
+            servers: Mutex::new(HashMap::new()),
+            server_pid: Mutex::new(None),
+            next_pid: AtomicU32::new(FIRST_PID),
+        }
+    }
+
+    pub(crate) fn respond(&self, matches: impl Fn(&Cmd) -> bool + Send + Sync + 'static, result: Result<Output, String>) {
+        self.respond_after(matches, Duration::ZERO, result);
+    }
+
+    // A response that takes `delay`, for timeouts and cancellation
+    pub(crate) fn respond_after(
+        &self,
+        matches: impl Fn(&Cmd) -> bool + Send + Sync + 'static,
+        delay: Duration,
+        result: Result<Output, String>,
+    ) {
+        self.responses.lock().unwrap().push(Response { matches: Box::new(matches), delay, result });
+    }
+
+    pub(crate) fn set_server_output(&self, output: &str) {
+        *self.server_output.lock().unwrap() = output.as_bytes().to_vec();
+    }
+
+    // Spawned servers take `pid`, e.g. the test's own so that a socket the
+    // test listens on passes the readiness probe's owner check. Such a server
+    // must not be stopped, that would signal the test itself.
+    pub(crate) fn set_server_pid(&self, pid: u32) {
+        *self.server_pid.lock().unwrap() = Some(pid);
+    }
+
+    // Every command run or spawned so far, in order
+    pub(crate) fn calls(&self) -> Vec<Cmd> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    // End a spawned server as if it had exited by itself
+    pub(crate) fn exit(&self, pid: u32, exit: Exit) {
+        if let Some(sender) = self.servers.lock().unwrap().get(&pid) {
+            let _ = sender.send(Some(exit));
+        }
+    }
+}
+
+impl Default for FakeRunner {
+    fn default() -> FakeRunner {
+        FakeRunner::new()
+    }
+}
+
+impl ProcessRunner for FakeRunner {
+    fn output<'a>(&'a self, cmd: &'a Cmd, timeout: Duration) -> BoxFuture<'a, Result<Output, String>> {
+        self.calls.lock().unwrap().push(cmd.clone());
+        let (delay, result) = {
+            let responses = self.responses.lock().unwrap();
+            match responses.iter().find(|response| (response.matches)(cmd)) {
+                Some(response) => (response.delay, response.result.clone()),
+                None => (Duration::ZERO, Ok(Output::ok(""))),
+            }
+        };
+
+        Box::pin(async move {
+            if delay > timeout {
+                tokio::time::sleep(timeout).await;
+                return Err(format!("{} did not finish within {} seconds", cmd.name(), timeout.as_secs()));
+            }
+            tokio::time::sleep(delay).await;
+            result
+        })
+    }
+
+    fn spawn(&self, cmd: &Cmd) -> Result<Box<dyn Process>, String> {
+        self.calls.lock().unwrap().push(cmd.clone());
+        let pid = match *self.server_pid.lock().unwrap() {
+            Some(pid) => pid,
+            None => self.next_pid.fetch_add(1, Ordering::SeqCst),
+        };
+        let (sender, exit) = watch::channel(None);
+        self.servers.lock().unwrap().insert(pid, sender.clone());
+        let output = Some(self.server_output.lock().unwrap().clone());
+        Ok(Box::new(FakeProcess { pid, output, sender, exit }))
+    }
+}
+
+struct FakeProcess {
+    pid: u32,
+    output: Option<Vec<u8>>,
+    sender: watch::Sender<Option<Exit>>,
+    exit: watch::Receiver<Option<Exit>>,
+}
+
+impl Process for FakeProcess {
+    fn id(&self) -> u32 {
+        self.pid
+    }
+
+    fn take_output(&mut self) -> Vec<OutputStream> {
+        match self.output.take() {
+            Some(output) => vec![Box::new(Cursor::new(output))],
+            None => Vec::new(),
+        }
+    }
+
+    fn try_wait(&mut self) -> Result<Option<Exit>, String> {
+        Ok(*self.exit.borrow())
+    }
+
+    fn wait(&mut self) -> BoxFuture<'_, Result<Exit, String>> {
+        Box::pin(async move {
+            let exit = self.exit.wait_for(Option::is_some).await.map_err(|e| e.to_string())?;
+            Ok(exit.unwrap_or_default())
+        })
+    }
+
+    fn kill(&mut self) -> Result<(), String> {
+        self.sender.send_if_modified(|exit| {
+            let running = exit.is_none();
+            if running {
+                *exit = Some(Exit { code: None, signal: Some(SIGKILL) });
+            }
+            running
+        });
+        Ok(())
+    }
+}
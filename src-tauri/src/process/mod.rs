@@ -0,0 +1,251 @@
+// Process runner. The programs the app runs go through `ProcessRunner`:
+// one-off commands (version checks, config tests, mysqld --initialize) run
+// with a timeout and captured output and are killed when their future is
+// dropped; servers keep running and hand their output to the log capture.
+// The real runner is built on tokio::process, so nothing blocks the async
+// runtime; the fake one lets service logic be tested without the binaries.
+// The OS queries in `platform` (lsof, ps, netstat, tasklist, taskkill, ...)
+// are synchronous; async code runs them through `blocking`.
+
+use std::ffi::{OsStr, OsString};
+use std::fmt;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::process::{ExitStatus, Stdio};
+use std::sync::{Arc, LazyLock, RwLock};
+use std::time::Duration;
+use tokio::io::AsyncRead;
+
+use crate::platform;
+
+#[cfg(test)]
+pub(crate) mod fake;
+
+pub(crate) type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+pub(crate) type OutputStream = Box<dyn AsyncRead + Send + Unpin>;
+
+static RUNNER: LazyLock<RwLock<Arc<dyn ProcessRunner>>> = LazyLock::new(|| RwLock::new(Arc::new(TokioRunner)));
+
+// A command line to run, kept as plain data so a fake runner can inspect it
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Cmd {
+    pub program: PathBuf,
+    pub args: Vec<OsString>,
+    pub envs: Vec<(OsString, OsString)>,
+    pub current_dir: Option<PathBuf>,
+}
+
+impl Cmd {
+    pub(crate) fn new(program: impl AsRef<Path>) -> Cmd {
+        Cmd { program: program.as_ref().to_path_buf(), ..Cmd::default() }
+    }
+
+    pub(crate) fn arg(mut self, arg: impl AsRef<OsStr>) -> Cmd {
+        self.args.push(arg.as_ref().to_os_string());
+        self
+    }
+
+    pub(crate) fn args<I, S>(mut self, args: I) -> Cmd
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        self.args.extend(args.into_iter().map(|arg| arg.as_ref().to_os_string()));
+        self
+    }
+
+    pub(crate) fn env(mut self, key: impl AsRef<OsStr>, value: impl AsRef<OsStr>) -> Cmd {
+        self.envs.push((key.as_ref().to_os_string(), value.as_ref().to_os_string()));
+        self
+    }
+
+    pub(crate) fn current_dir(mut self, dir: impl AsRef<Path>) -> Cmd {
+        self.current_dir = Some(dir.as_ref().to_path_buf());
+        self
+    }
+
+    // File name of the program, for messages
+    pub(crate) fn name(&self) -> String {
+        self.program
+            .file_name()
+            .unwrap_or(self.program.as_os_str())
+            .to_string_lossy()
+            .to_string()
+    }
+
+    fn command(&self) -> tokio::process::Command {
+        let mut command = tokio::process::Command::new(&self.program);
+        command.args(&self.args).envs(self.envs.iter().map(|(key, value)| (key, value)));
+        if let Some(dir) = &self.current_dir {
+            command.current_dir(dir);
+        }
+        command
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Output {
+    // None when a signal ended the process
+    pub code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl Output {
+    pub(crate) fn success(&self) -> bool {
+        self.code == Some(0)
+    }
+}
+
+// How a process ended
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct Exit {
+    pub code: Option<i32>,
+    // Unix signal that ended it
+    pub signal: Option<i32>,
+}
+
+impl Exit {
+    fn from_status(status: ExitStatus) -> Exit {
+        Exit { code: status.code(), signal: platform::exit_signal(&status) }
+    }
+}
+
+impl fmt::Display for Exit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.code, self.signal) {
+            (Some(code), _) => write!(f, "exit code {}", code),
+            (None, Some(signal)) => write!(f, "signal {}", signal),
+            (None, None) => write!(f, "an unknown status"),
+        }
+    }
+}
+
+// A server started through the runner
+pub(crate) trait Process: Send {
+    fn id(&self) -> u32;
+
+    // stdout and stderr, handed out once
+    fn take_output(&mut self) -> Vec<OutputStream>;
+
+    // How the process ended, if it has
+    fn try_wait(&mut self) -> Result<Option<Exit>, String>;
+
+    fn wait(&mut self) -> BoxFuture<'_, Result<Exit, String>>;
+
+    // Kill it without waiting for it to go
+    fn kill(&mut self) -> Result<(), String>;
+}
+
+pub(crate) trait ProcessRunner: Send + Sync {
+    // Run a command to completion and collect its output. It is killed once
+    // `timeout` has passed or when the returned future is dropped.
+    fn output<'a>(&'a self, cmd: &'a Cmd, timeout: Duration) -> BoxFuture<'a, Result<Output, String>>;
+
+    // Start a server. It keeps running when the handle is dropped; stdout and
+    // stderr are piped for capturing unless the command discards them.
+    fn spawn(&self, cmd: &Cmd) -> Result<Box<dyn Process>, String>;
+}
+
+pub(crate) fn runner() -> Arc<dyn ProcessRunner> {
+    match RUNNER.read() {
+        Ok(runner) => runner.clone(),
+        Err(poisoned) => poisoned.into_inner().clone(),
+    }
+}
+
+// Swap in another runner, the fake one in tests
+#[cfg(test)]
+pub(crate) fn set_runner(runner: Arc<dyn ProcessRunner>) {
+    match RUNNER.write() {
+        Ok(mut current) => *current = runner,
+        Err(poisoned) => *poisoned.into_inner() = runner,
+    }
+}
+
+// Run blocking work on the runtime's blocking pool instead of an async worker
+pub(crate) async fn blocking<T: Send + 'static>(work: impl FnOnce() -> T + Send + 'static) -> T {
+    match tokio::task::spawn_blocking(work).await {
+        Ok(result) => result,
+        Err(e) => std::panic::resume_unwind(e.into_panic()),
+    }
+}
+
+pub(crate) struct TokioRunner;
+
+impl ProcessRunner for TokioRunner {
+    fn output<'a>(&'a self, cmd: &'a Cmd, timeout: Duration) -> BoxFuture<'a, Result<Output, String>> {
+        Box::pin(async move {
+            let child = cmd
+                .command()
+                .stdin(Stdio::null())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .kill_on_drop(true)
+                .spawn()
+                .map_err(|e| format!("Failed to run {}: {}", cmd.name(), e))?;
+
+            // Giving up on the wait drops the child, which kills it
+            match tokio::time::timeout(timeout, child.wait_with_output()).await {
+                Ok(Ok(output)) => Ok(Output {
+                    code: output.status.code(),
+                    stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+                    stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+                }),
+                Ok(Err(e)) => Err(format!("Failed to run {}: {}", cmd.name(), e)),
+                Err(_) => Err(format!("{} did not finish within {} seconds", cmd.name(), timeout.as_secs())),
+            }
+        })
+    }
+
+    fn spawn(&self, cmd: &Cmd) -> Result<Box<dyn Process>, String> {
+        let child = cmd
+            .command()
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to start {}: {}", cmd.name(), e))?;
+        let pid = child.id().unwrap_or_default();
+        Ok(Box::new(TokioProcess { pid, child }))
+    }
+}
+
+struct TokioProcess {
+    pid: u32,
+    child: tokio::process::Child,
+}
+
+impl Process for TokioProcess {
+    fn id(&self) -> u32 {
+        self.pid
+    }
+
+    fn take_output(&mut self) -> Vec<OutputStream> {
+        let mut streams: Vec<OutputStream> = Vec::new();
+        if let Some(stdout) = self.child.stdout.take() {
+            streams.push(Box::new(stdout));
+        }
+        if let Some(stderr) = self.child.stderr.take() {
+            streams.push(Box::new(stderr));
+        }
+        streams
+    }
+
+    fn try_wait(&mut self) -> Result<Option<Exit>, String> {
+        self.child
+            .try_wait()
+            .map(|status| status.map(Exit::from_status))
+            .map_err(|e| e.to_string())
+    }
+
+    fn wait(&mut self) -> BoxFuture<'_, Result<Exit, String>> {
+        Box::pin(async move { self.child.wait().await.map(Exit::from_status).map_err(|e| e.to_string()) })
+    }
+
+    fn kill(&mut self) -> Result<(), String> {
+        self.child.start_kill().map_err(|e| e.to_string())
+    }
+}
@@ -0,0 +1,284 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use super::{config, rewrite_lines, version_after, Probe, Service, ServiceContext};
+use crate::process::{self, BoxFuture, Cmd};
+use crate::{backups, logs, php, platform, templates};
+use crate::settings::{self, ServiceSettings, Settings};
+use crate::templates::Vars;
+
+pub(crate) struct Apache;
+
+impl Service for Apache {
+    fn name(&self) -> &'static str {
+        "apache"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Apache"
+    }
+
+    fn bundled_dir(&self, base_path: &Path) -> PathBuf {
+        base_path.join("apache")
+    }
+
+    fn binary_names(&self) -> &'static [&'static str] {
+        &["httpd", "apache2"]
+    }
+
+    fn config_path(&self, base_path: &Path) -> PathBuf {
+        base_path.join("config").join("httpd.conf")
+    }
+
+    fn config_template(&self) -> &'static str {
+        include_str!("../../templates/httpd.conf")
+    }
+
+    fn template_vars(&self, base_path: &Path, settings: &Settings) -> Vars {
+        // System Apache packages keep modules and mime.types outside our tree
+        let apache_path = self.binary(base_path).unwrap_or_else(|| self.expected_binary(base_path));
+        let (server_root, types_config) = platform::apache_layout(&base_path.join("apache"), &apache_path);
+
+        Vars::from([
+            ("server_root", platform::config_path(&server_root)),
+            ("types_config", platform::config_path(&types_config)),
+            ("platform_modules", platform::apache_platform_modules().to_string()),
+            ("server_name", settings.apache.server_name.clone()),
+            ("document_root", platform::config_path(&settings.document_root(base_path))),
+            ("php_config", php::switch::apache_php_block(base_path, &php::switch::current_dir(base_path))),
+        ])
+    }
+
+    fn default_port(&self) -> u16 {
+        80
+    }
+
+    fn service_settings<'a>(&self, settings: &'a Settings) -> &'a ServiceSettings {
+        &settings.apache.service
+    }
+
+    fn service_settings_mut<'a>(&self, settings: &'a mut Settings) -> &'a mut ServiceSettings {
+        &mut settings.apache.service
+    }
+
+    fn apply_settings(&self, base_path: &Path, previous: &Settings, current: &Settings) -> Result<(), String> {
+        let config_path = self.config_path(base_path);
+        let root_changed = previous.paths.document_root != current.paths.document_root;
+        let name_changed = previous.apache.server_name != current.apache.server_name;
+        if !config_path.exists() || !(root_changed || name_changed) {
+            return Ok(());
+        }
+
+        let old_root = platform::config_path(&previous.document_root(base_path));
+        let new_root = platform::config_path(&current.document_root(base_path));
+        let content = std::fs::read_to_string(&config_path).map_err(|e| e.to_string())?;
+
+        let updated = rewrite_lines(&content, |line| {
+            let trimmed = line.trim_start();
+            let indent = &line[..line.len() - trimmed.len()];
+            if root_changed && trimmed.starts_with("DocumentRoot ") {
+                Some(format!("{}DocumentRoot \"{}\"", indent, new_root))
+            } else if root_changed && trimmed.starts_with("<Directory ") && trimmed.contains(old_root.as_str()) {
+                Some(format!("{}{}", indent, trimmed.replace(old_root.as_str(), &new_root)))
+            } else if let (true, Some(value)) = (name_changed, trimmed.strip_prefix("ServerName ")) {
+                // Keep the port half of "host:port"
+                let port = value.trim().rsplit_once(':').map(|(_, port)| format!(":{}", port));
+                Some(format!("{}ServerName {}{}", indent, current.apache.server_name, port.unwrap_or_default()))
+            } else {
+                None
+            }
+        });
+
+        backups::write_config_file(base_path, &config_path, &updated, "Settings updated")
+    }
+
+    fn parse_port(&self, config: &str) -> Option<u16> {
+        // "Listen 80", "Listen 127.0.0.1:8080" or "Listen [::]:80"
+        config.lines().find_map(|line| {
+            let value = line.trim().strip_prefix("Listen ")?.split_whitespace().next()?;
+            value.rsplit(':').next()?.parse().ok()
+        })
+    }
+
+    fn rewrite_port(&self, config: &str, port: u16) -> String {
+        rewrite_lines(config, |line| {
+            let trimmed = line.trim_start();
+            let indent = &line[..line.len() - trimmed.len()];
+            if let Some(value) = trimmed.strip_prefix("Listen ") {
+                let value = value.trim();
+                let address = value.rfind(':').map(|index| &value[..=index]).unwrap_or("");
+                Some(format!("{}Listen {}{}", indent, address, port))
+            } else if let Some(value) = trimmed.strip_prefix("ServerName ") {
+                let host = value.trim().split(':').next().unwrap_or("localhost");
+                Some(format!("{}ServerName {}:{}", indent, host, port))
+            } else {
+                None
+            }
+        })
+    }
+
+    fn prepare<'a>(&'a self, ctx: &'a ServiceContext) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            // Projects pinned to their own PHP version need their FastCGI pools
+            let settings = settings::load(&ctx.base_path)?;
+            write_phpmyadmin_config(&ctx.base_path)?;
+            php::projects::write_projects_config(&ctx.base_path, &settings)?;
+            php::projects::sync_pools(&ctx.base_path, &settings)?;
+
+            // Test Apache configuration first
+            test_config(&ctx.base_path, &ctx.binary, &ctx.config_path).await
+        })
+    }
+
+    fn start_command(&self, ctx: &ServiceContext) -> Cmd {
+        // Run from the DevStackBox root, like the config test
+        Cmd::new(&ctx.binary)
+            .arg("-f")
+            .arg(&ctx.config_path)
+            .arg("-D")
+            .arg("FOREGROUND")
+            .current_dir(&ctx.base_path)
+            // php8ts.dll and extension dependencies are found through PATH
+            .env("PATH", php::switch::path_env(&ctx.base_path))
+    }
+
+    fn probe(&self) -> Probe {
+        Probe::Http { path: "/" }
+    }
+
+    // graceful-stop lets in-flight requests finish. The Windows MPM only knows
+    // the plain shutdown, which still waits for its worker threads.
+    fn request_stop<'a>(&'a self, base_path: &'a Path, _pid: u32) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(self.control(base_path, if cfg!(windows) { "shutdown" } else { "graceful-stop" }))
+    }
+
+    // The PHP pools serve Apache's requests, so they go only once it has
+    fn after_stop(&self) {
+        php::projects::stop_pools();
+    }
+
+    // A graceful restart re-reads the config (and php.ini) while requests in
+    // flight finish on the old workers. A config that fails the test is never
+    // handed to the running server.
+    fn reload<'a>(&'a self, base_path: &'a Path, _pid: u32) -> BoxFuture<'a, Result<bool, String>> {
+        Box::pin(async move {
+            let binary = self.binary(base_path).ok_or("Apache binary not found")?;
+            let settings = settings::load(base_path)?;
+            write_phpmyadmin_config(base_path)?;
+            php::projects::write_projects_config(base_path, &settings)?;
+            php::projects::sync_pools(base_path, &settings)?;
+            test_config(base_path, &binary, &self.config_path(base_path)).await?;
+
+            // The Windows MPM has no graceful restart, its restart still lets
+            // running requests finish
+            self.control(base_path, if cfg!(windows) { "restart" } else { "graceful" }).await?;
+            Ok(true)
+        })
+    }
+
+    fn log_files(&self, base_path: &Path) -> Vec<(&'static str, PathBuf)> {
+        let config = std::fs::read_to_string(self.config_path(base_path)).unwrap_or_default();
+        let server_root = directive_path(&config, "ServerRoot", Path::new(""))
+            .unwrap_or_else(|| self.bundled_dir(base_path));
+        let logs_dir = logs::logs_dir(base_path);
+        vec![
+            ("error", directive_path(&config, "ErrorLog", &server_root).unwrap_or_else(|| logs_dir.join("error.log"))),
+            ("access", directive_path(&config, "CustomLog", &server_root).unwrap_or_else(|| logs_dir.join("access.log"))),
+        ]
+    }
+
+    // A graceful restart reopens the logs without dropping requests
+    fn reopen_signal(&self) -> Option<platform::ReopenSignal> {
+        Some(platform::ReopenSignal::Graceful)
+    }
+
+    // PidFile, relative to the server root like the logs
+    fn pid_file(&self, base_path: &Path) -> Option<PathBuf> {
+        let config = std::fs::read_to_string(self.config_path(base_path)).unwrap_or_default();
+        let server_root = directive_path(&config, "ServerRoot", Path::new(""))
+            .unwrap_or_else(|| self.bundled_dir(base_path));
+        directive_path(&config, "PidFile", &server_root).or_else(|| Some(server_root.join("logs").join("httpd.pid")))
+    }
+
+    fn version_args(&self) -> &'static [&'static str] {
+        &["-v"]
+    }
+
+    fn parse_version(&self, output: &str) -> Option<String> {
+        // "Server version: Apache/2.4.62 (Win64)"
+        version_after(output, "Apache/")
+    }
+}
+
+// httpd -t and -k return as soon as they have parsed the config
+const CONTROL_TIMEOUT: Duration = Duration::from_secs(30);
+
+impl Apache {
+    // Send the running server a command through `httpd -k`, which finds it by PidFile
+    async fn control(&self, base_path: &Path, command: &str) -> Result<(), String> {
+        let binary = self.binary(base_path).ok_or("Apache binary not found")?;
+        let cmd = Cmd::new(&binary)
+            .arg("-f")
+            .arg(self.config_path(base_path))
+            .arg("-k")
+            .arg(command)
+            .current_dir(base_path)
+            .env("PATH", php::switch::path_env(base_path));
+        let output = process::runner().output(&cmd, CONTROL_TIMEOUT).await?;
+        if output.success() {
+            Ok(())
+        } else {
+            Err(output.stderr.trim().to_string())
+        }
+    }
+}
+
+// The /phpmyadmin alias lives in the include directory, so regenerating
+// httpd.conf keeps it. Only written when missing; the user may edit it.
+fn write_phpmyadmin_config(base_path: &Path) -> Result<(), String> {
+    let phpmyadmin_dir = base_path.join("phpmyadmin");
+    let path = config::include_dir(&Apache, base_path).join("phpmyadmin.conf");
+    if !phpmyadmin_dir.is_dir() || path.exists() {
+        return Ok(());
+    }
+    let vars = Vars::from([("phpmyadmin_dir", platform::config_path(&phpmyadmin_dir))]);
+    let rendered = templates::render(include_str!("../../templates/phpmyadmin.conf"), &vars)?;
+    backups::write_config_file(base_path, &path, &rendered, "Generated phpMyAdmin config")
+}
+
+// httpd -t, so a broken config is reported instead of taking Apache down
+async fn test_config(base_path: &Path, binary: &Path, config_path: &Path) -> Result<(), String> {
+    let cmd = Cmd::new(binary)
+        .arg("-f")
+        .arg(config_path)
+        .arg("-t")
+        .current_dir(base_path)
+        .env("PATH", php::switch::path_env(base_path));
+    match process::runner().output(&cmd, CONTROL_TIMEOUT).await {
+        Ok(output) => {
+            if output.success() {
+                Ok(())
+            } else {
+                Err(format!("Apache configuration test failed: {}", output.stderr))
+            }
+        }
+        Err(e) => Err(format!("Failed to test Apache configuration: {}", e)),
+    }
+}
+
+// File named by the first `directive` in httpd.conf, relative paths resolved
+// against the server root as Apache does. Piped and syslog logs have no file.
+fn directive_path(config: &str, directive: &str, server_root: &Path) -> Option<PathBuf> {
+    let value = config.lines().find_map(|line| {
+        let rest = line.trim().strip_prefix(directive)?;
+        rest.starts_with(char::is_whitespace).then(|| rest.trim())
+    })?;
+    let value = match value.strip_prefix('"') {
+        Some(quoted) => quoted.split('"').next()?,
+        None => value.split_whitespace().next()?,
+    };
+    if value.is_empty() || value.starts_with('|') || value.starts_with("syslog") {
+        return None;
+    }
+    Some(server_root.join(value))
+}
@@ -0,0 +1,98 @@
+// Config generation. Each service renders its config from a template (the
+// built-in one, or config/templates/<file> when the user provides one) and the
+// settings file. Regeneration keeps the USER blocks of the current file, and
+// everything in the service's include directory is left alone.
+
+use std::path::{Path, PathBuf};
+
+use super::{find_service, Service};
+use crate::get_project_root;
+use crate::{backups, platform, settings, templates};
+
+#[derive(serde::Serialize)]
+pub struct ConfigPreview {
+    service: String,
+    path: String,
+    // Template the config was rendered from
+    template: String,
+    rendered: String,
+    current: Option<String>,
+    changed: bool,
+}
+
+// Directory for drop-in fragments the generated config includes
+pub(crate) fn include_dir(service: &dyn Service, base_path: &Path) -> PathBuf {
+    base_path.join("config").join(format!("{}.d", service.name()))
+}
+
+fn user_template_path(service: &dyn Service, base_path: &Path) -> Option<PathBuf> {
+    let file_name = service.config_path(base_path).file_name()?.to_owned();
+    Some(base_path.join("config").join("templates").join(file_name))
+}
+
+// The user's template overrides the built-in one
+fn load_template(service: &dyn Service, base_path: &Path) -> Result<(String, String), String> {
+    match user_template_path(service, base_path).filter(|path| path.exists()) {
+        Some(path) => {
+            let template = std::fs::read_to_string(&path)
+                .map_err(|e| format!("Failed to read template {}: {}", path.display(), e))?;
+            Ok((path.display().to_string(), template))
+        }
+        None => Ok(("built-in".to_string(), service.config_template().to_string())),
+    }
+}
+
+fn render_with_source(service: &dyn Service, base_path: &Path) -> Result<(String, String), String> {
+    let settings = settings::load(base_path)?;
+    let (source, template) = load_template(service, base_path)?;
+
+    let mut vars = service.template_vars(base_path, &settings);
+    vars.insert("base_path", platform::config_path(base_path));
+    vars.insert("include_dir", platform::config_path(&include_dir(service, base_path)));
+    vars.insert("port", service.service_settings(&settings).port.to_string());
+
+    let rendered = templates::render(&template, &vars).map_err(|e| format!("{} template: {}", source, e))?;
+    let rendered = match std::fs::read_to_string(service.config_path(base_path)) {
+        Ok(existing) => templates::merge_user_blocks(&rendered, &existing),
+        Err(_) => rendered,
+    };
+    Ok((source, rendered))
+}
+
+pub(crate) fn render_config(service: &dyn Service, base_path: &Path) -> Result<String, String> {
+    render_with_source(service, base_path).map(|(_, rendered)| rendered)
+}
+
+pub(crate) fn write_config(service: &dyn Service, base_path: &Path, reason: &str) -> Result<(), String> {
+    let rendered = render_config(service, base_path)?;
+
+    // The include directory (inside config/) must exist, MySQL refuses to start otherwise
+    std::fs::create_dir_all(include_dir(service, base_path)).map_err(|e| e.to_string())?;
+    backups::write_config_file(base_path, &service.config_path(base_path), &rendered, reason)
+}
+
+#[tauri::command]
+pub async fn preview_config(name: String) -> Result<ConfigPreview, String> {
+    let service = find_service(&name)?;
+    let base_path = get_project_root()?;
+    let path = service.config_path(&base_path);
+
+    let (template, rendered) = render_with_source(service, &base_path)?;
+    let current = std::fs::read_to_string(&path).ok();
+
+    Ok(ConfigPreview {
+        service: service.name().to_string(),
+        path: path.display().to_string(),
+        template,
+        changed: current.as_deref() != Some(rendered.as_str()),
+        rendered,
+        current,
+    })
+}
+
+#[tauri::command]
+pub async fn regenerate_config(name: String) -> Result<bool, String> {
+    let service = find_service(&name)?;
+    write_config(service, &get_project_root()?, "Regenerated from template")?;
+    Ok(true)
+}
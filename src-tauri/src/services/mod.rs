@@ -0,0 +1,695 @@
+// Service abstraction and registry. Each managed server (MySQL, Apache, ...)
+// implements `Service`; the generic commands below drive any of them, so adding
+// a new server means adding one implementation to `registry()`.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::jobs::{self, JobHandle};
+use crate::process::{self, BoxFuture, Cmd, Process};
+use crate::settings::{self, ServiceSettings, Settings};
+use crate::templates::Vars;
+use crate::{get_project_root, logs, platform};
+
+mod apache;
+pub(crate) mod config;
+mod mysql;
+pub(crate) mod ports;
+mod probe;
+pub(crate) mod profiles;
+pub(crate) mod reconcile;
+pub(crate) mod supervisor;
+
+pub(crate) use apache::Apache;
+pub(crate) use mysql::MySql;
+pub(crate) use probe::{Probe, Readiness};
+
+// Service status and process tracking
+pub(crate) static SERVICE_STATUS: LazyLock<Arc<Mutex<HashMap<String, bool>>>> =
+    LazyLock::new(|| Arc::new(Mutex::new(HashMap::new())));
+
+pub(crate) static SERVICE_PROCESSES: LazyLock<Arc<Mutex<HashMap<String, u32>>>> =
+    LazyLock::new(|| Arc::new(Mutex::new(HashMap::new())));
+
+// One lock per service, held from the running check until the new state is
+// recorded, so two starts (or a start and a stop) cannot interleave
+static SERVICE_LOCKS: LazyLock<Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+// How often stop() checks whether the server has exited
+const STOP_POLL: Duration = Duration::from_millis(250);
+
+// `--version` and friends, shared with the PHP version checks
+pub(crate) const VERSION_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(serde::Serialize)]
+pub struct ServiceInfo {
+    pub running: bool,
+    pub pid: Option<u32>,
+    pub port: Option<u16>,
+    pub version: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+pub struct ServiceListing {
+    name: String,
+    display_name: String,
+    installed: bool,
+    #[serde(flatten)]
+    info: ServiceInfo,
+}
+
+// Everything a service needs to know about where it is running from
+pub(crate) struct ServiceContext {
+    pub base_path: PathBuf,
+    pub binary: PathBuf,
+    pub config_path: PathBuf,
+    // The job starting the service, for progress and cancellation
+    pub job: JobHandle,
+}
+
+pub(crate) trait Service: Send + Sync {
+    // Registry key, also used for the status maps ("mysql", "apache")
+    fn name(&self) -> &'static str;
+
+    // Human readable name for messages ("MySQL", "Apache")
+    fn display_name(&self) -> &'static str;
+
+    // Directory the bundled copy lives in (e.g. <root>/mysql)
+    fn bundled_dir(&self, base_path: &Path) -> PathBuf;
+
+    // Executable names to look for, preferred first
+    fn binary_names(&self) -> &'static [&'static str];
+
+    fn binary(&self, base_path: &Path) -> Option<PathBuf> {
+        platform::resolve_binary(&self.bundled_dir(base_path), self.binary_names())
+    }
+
+    // Where the bundled binary is expected, used in "not found" messages
+    fn expected_binary(&self, base_path: &Path) -> PathBuf {
+        self.bundled_dir(base_path)
+            .join("bin")
+            .join(platform::exe_name(self.binary_names()[0]))
+    }
+
+    fn config_path(&self, base_path: &Path) -> PathBuf;
+
+    // Built-in config template, see `config` for how it is rendered
+    fn config_template(&self) -> &'static str;
+
+    // Values for the template placeholders; base_path, include_dir and port
+    // are filled in for every service
+    fn template_vars(&self, base_path: &Path, settings: &Settings) -> Vars;
+
+    fn default_port(&self) -> u16;
+
+    // Read the listening port out of the service's config file
+    fn parse_port(&self, config: &str) -> Option<u16>;
+
+    // Return the config file content with the listening port replaced
+    fn rewrite_port(&self, config: &str, port: u16) -> String;
+
+    // Update other configs that connect to this service (e.g. phpMyAdmin)
+    fn apply_port_to_clients(&self, _base_path: &Path, _port: u16) -> Result<(), String> {
+        Ok(())
+    }
+
+    // This service's section of the settings file
+    fn service_settings<'a>(&self, settings: &'a Settings) -> &'a ServiceSettings;
+
+    fn service_settings_mut<'a>(&self, settings: &'a mut Settings) -> &'a mut ServiceSettings;
+
+    // Carry changed settings (other than the port) into the existing config file
+    fn apply_settings(&self, _base_path: &Path, _previous: &Settings, _current: &Settings) -> Result<(), String> {
+        Ok(())
+    }
+
+    // The port the service is configured to listen on
+    fn port(&self, base_path: &Path) -> u16 {
+        settings::load(base_path)
+            .map(|settings| self.service_settings(&settings).port)
+            .unwrap_or_else(|_| self.default_port())
+    }
+
+    // One-off work before spawning (data directory init, config test, ...)
+    fn prepare<'a>(&'a self, _ctx: &'a ServiceContext) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async { Ok(()) })
+    }
+
+    fn start_command(&self, ctx: &ServiceContext) -> Cmd;
+
+    // Protocol-level check that the server is accepting work
+    fn probe(&self) -> Probe {
+        Probe::Tcp
+    }
+
+    // Backoff for the readiness probe; the timeout comes from settings
+    fn readiness(&self) -> Readiness {
+        Readiness::default()
+    }
+
+    // Ask the server to shut down cleanly. stop() gives it stop_timeout_secs
+    // to exit and kills it after that.
+    fn request_stop<'a>(&'a self, _base_path: &'a Path, pid: u32) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(process::blocking(move || platform::stop_process(pid)))
+    }
+
+    // Clean up after the server has exited (helper processes, ...)
+    fn after_stop(&self) {}
+
+    // Make the running server pick up config changes without stopping it.
+    // Ok(false) when it cannot, it is restarted instead.
+    fn reload<'a>(&'a self, _base_path: &'a Path, _pid: u32) -> BoxFuture<'a, Result<bool, String>> {
+        Box::pin(async { Ok(false) })
+    }
+
+    // Logs the server writes itself, by name ("error", "access"). Its console
+    // output is always captured to logs/<name>.log as well.
+    fn log_files(&self, _base_path: &Path) -> Vec<(&'static str, PathBuf)> {
+        Vec::new()
+    }
+
+    // How to make the running server reopen log_files() after rotation moved
+    // them away; None if it cannot, they are then copied and truncated
+    fn reopen_signal(&self) -> Option<platform::ReopenSignal> {
+        None
+    }
+
+    // File the server writes its PID to, used to find it again after the app
+    // restarted while it kept running
+    fn pid_file(&self, _base_path: &Path) -> Option<PathBuf> {
+        None
+    }
+
+    fn version_args(&self) -> &'static [&'static str];
+
+    fn parse_version(&self, output: &str) -> Option<String>;
+}
+
+pub(crate) fn registry() -> &'static [Box<dyn Service>] {
+    static REGISTRY: LazyLock<Vec<Box<dyn Service>>> =
+        LazyLock::new(|| vec![Box::new(MySql), Box::new(Apache)]);
+    &REGISTRY
+}
+
+pub(crate) fn find_service(name: &str) -> Result<&'static dyn Service, String> {
+    registry()
+        .iter()
+        .find(|service| service.name() == name)
+        .map(|service| service.as_ref())
+        .ok_or_else(|| format!("Unknown service: {}", name))
+}
+
+// Pull the version token that follows `marker` ("Ver 8.0.35 for ..." -> "8.0.35")
+pub(crate) fn version_after(output: &str, marker: &str) -> Option<String> {
+    let start = output.find(marker)? + marker.len();
+    let end = output[start..].find(char::is_whitespace)?;
+    Some(output[start..start + end].to_string())
+}
+
+// Rewrite a config file line by line; `edit` returns the replacement for the
+// lines it wants to change. Line endings of the original are preserved.
+pub(crate) fn rewrite_lines(content: &str, mut edit: impl FnMut(&str) -> Option<String>) -> String {
+    content
+        .split_inclusive('\n')
+        .map(|line| {
+            let body = line.trim_end_matches(['\r', '\n']);
+            match edit(body) {
+                Some(replacement) => format!("{}{}", replacement, &line[body.len()..]),
+                None => line.to_string(),
+            }
+        })
+        .collect()
+}
+
+pub(crate) async fn service_version(service: &dyn Service, base_path: &Path) -> Option<String> {
+    let cmd = Cmd::new(service.binary(base_path)?).args(service.version_args());
+    let output = process::runner().output(&cmd, VERSION_TIMEOUT).await.ok()?;
+    service.parse_version(&output.stdout)
+}
+
+pub(crate) fn tracked_pid(name: &str) -> Result<Option<u32>, String> {
+    let running = {
+        let status = SERVICE_STATUS.lock().map_err(|e| e.to_string())?;
+        *status.get(name).unwrap_or(&false)
+    };
+
+    if running {
+        let processes = SERVICE_PROCESSES.lock().map_err(|e| e.to_string())?;
+        Ok(processes.get(name).cloned())
+    } else {
+        Ok(None)
+    }
+}
+
+fn set_running(name: &str, pid: Option<u32>) -> Result<(), String> {
+    // Update service status
+    {
+        let mut status = SERVICE_STATUS.lock().map_err(|e| e.to_string())?;
+        status.insert(name.to_string(), pid.is_some());
+    }
+
+    // Store or drop the process PID
+    {
+        let mut processes = SERVICE_PROCESSES.lock().map_err(|e| e.to_string())?;
+        match pid {
+            Some(pid) => processes.insert(name.to_string(), pid),
+            None => processes.remove(name),
+        };
+    }
+    Ok(())
+}
+
+fn service_lock(name: &str) -> Arc<tokio::sync::Mutex<()>> {
+    let mut locks = match SERVICE_LOCKS.lock() {
+        Ok(locks) => locks,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    locks.entry(name.to_string()).or_default().clone()
+}
+
+pub(crate) async fn status_of(service: &dyn Service) -> Result<ServiceInfo, String> {
+    let base_path = get_project_root()?;
+    let pid = tracked_pid(service.name())?;
+
+    Ok(ServiceInfo {
+        running: pid.is_some(),
+        pid,
+        port: Some(service.port(&base_path)),
+        version: service_version(service, &base_path).await,
+    })
+}
+
+pub(crate) async fn start(service: &dyn Service, job: &JobHandle) -> Result<bool, String> {
+    let lock = service_lock(service.name());
+    let _guard = lock.lock().await;
+    start_locked(service, job).await
+}
+
+async fn start_locked(service: &dyn Service, job: &JobHandle) -> Result<bool, String> {
+    // Get the project root directory (DevStackBox)
+    let base_path = get_project_root()?;
+
+    if tracked_pid(service.name())?.is_some() {
+        return Err(format!("{} is already running", service.display_name()));
+    }
+
+    let binary = match service.binary(&base_path) {
+        Some(path) => path,
+        None => return Err(format!(
+            "{} binary not found at {}. Please ensure {} is installed.",
+            service.display_name(),
+            service.expected_binary(&base_path).display(),
+            service.display_name()
+        )),
+    };
+
+    let config_path = service.config_path(&base_path);
+    if !config_path.exists() {
+        config::write_config(service, &base_path, "Generated default config")?;
+    }
+
+    // Refuse to start into a port conflict, and say who holds the port
+    let port_check = ports::check_port(service, &base_path).await;
+    if !port_check.available {
+        return Err(port_check.conflict_message());
+    }
+    let settings = settings::load(&base_path)?;
+    let service_settings = service.service_settings(&settings);
+    let port = service_settings.port;
+    let readiness = Readiness {
+        timeout: std::time::Duration::from_secs(service_settings.startup_timeout_secs),
+        ..service.readiness()
+    };
+
+    let ctx = ServiceContext { base_path, binary, config_path, job: job.clone() };
+    job.phase("Preparing");
+    service.prepare(&ctx).await?;
+    job.check_cancelled()?;
+
+    let mut child = process::runner()
+        .spawn(&service.start_command(&ctx))
+        .map_err(|e| format!("Failed to start {}: {}", service.display_name(), e))?;
+    let console_log = logs::console_log(&ctx.base_path, service.name());
+    let policy = logs::rotate::Policy::from_settings(&settings.logs);
+    if let Err(e) = logs::capture::capture(child.as_mut(), &console_log, policy) {
+        discard(child).await;
+        return Err(e);
+    }
+
+    // Wait until the server answers on its port, then record it as running
+    job.phase(&format!("Waiting for {} to accept connections", service.display_name()));
+    match probe::wait_until_ready(service, child.as_mut(), port, readiness, job).await {
+        Ok(()) => {
+            set_running(service.name(), Some(child.id()))?;
+            supervisor::watch(service.name(), child);
+            Ok(true)
+        }
+        Err(e) => {
+            discard(child).await;
+            Err(format!("{} failed to start: {}", service.display_name(), e))
+        }
+    }
+}
+
+// Don't leave a half-started process behind
+async fn discard(mut child: Box<dyn Process>) {
+    let _ = child.kill();
+    let _ = child.wait().await;
+}
+
+pub(crate) async fn stop(service: &dyn Service) -> Result<bool, String> {
+    let lock = service_lock(service.name());
+    let _guard = lock.lock().await;
+    stop_locked(service).await
+}
+
+async fn stop_locked(service: &dyn Service) -> Result<bool, String> {
+    let pid = match tracked_pid(service.name())? {
+        Some(pid) => pid,
+        // Stopping a crashed service calls off its pending restart
+        None if supervisor::cancel_restart(service.name()) => return Ok(true),
+        None => return Err(format!("{} is not running", service.display_name())),
+    };
+
+    let base_path = get_project_root()?;
+    let timeout = settings::load(&base_path)
+        .map(|settings| service.service_settings(&settings).stop_timeout_secs)
+        .unwrap_or(30);
+
+    supervisor::expect_exit(service.name(), true);
+    match shutdown(service, &base_path, pid, Duration::from_secs(timeout)).await {
+        Ok(()) => {
+            set_running(service.name(), None)?;
+            service.after_stop();
+            Ok(true)
+        }
+        Err(e) => {
+            supervisor::expect_exit(service.name(), false);
+            Err(format!("Failed to stop {}: {}", service.display_name(), e))
+        }
+    }
+}
+
+// Ask the server to stop and wait for it to exit; one that fails to shut down
+// cleanly within `timeout` is killed
+async fn shutdown(service: &dyn Service, base_path: &Path, pid: u32, timeout: Duration) -> Result<(), String> {
+    if let Err(e) = service.request_stop(base_path, pid).await {
+        println!("Graceful stop of {} failed, killing it: {}", service.display_name(), e);
+        return process::blocking(move || platform::kill_process(pid)).await;
+    }
+
+    let deadline = Instant::now() + timeout;
+    while process::blocking(move || platform::process_alive(pid)).await {
+        if Instant::now() >= deadline {
+            println!("{} did not stop within {} seconds, killing it", service.display_name(), timeout.as_secs());
+            return process::blocking(move || platform::kill_process(pid)).await;
+        }
+        tokio::time::sleep(STOP_POLL).await;
+    }
+    Ok(())
+}
+
+// Stop every running service, the reverse of registry order so Apache goes
+// before the database its sites use. Failures are logged and skipped.
+pub(crate) async fn stop_all() {
+    for service in registry().iter().rev() {
+        if matches!(tracked_pid(service.name()), Ok(Some(_))) {
+            if let Err(e) = stop(service.as_ref()).await {
+                println!("{}", e);
+            }
+        }
+    }
+}
+
+// Names of the services currently running
+pub(crate) fn running_services() -> Vec<String> {
+    registry()
+        .iter()
+        .filter(|service| matches!(tracked_pid(service.name()), Ok(Some(_))))
+        .map(|service| service.name().to_string())
+        .collect()
+}
+
+// Stop the service if it runs and start it again. stop() has waited for the
+// old server to shut down cleanly; its port may take a moment longer.
+async fn restart(service: &'static dyn Service, job: &JobHandle) -> Result<bool, String> {
+    let lock = service_lock(service.name());
+    let _guard = lock.lock().await;
+    if tracked_pid(service.name())?.is_some() {
+        job.phase(&format!("Stopping {}", service.display_name()));
+        stop_locked(service).await?;
+
+        let base_path = get_project_root()?;
+        for _ in 0..50 {
+            if ports::check_port(service, &base_path).await.available {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(200)).await;
+        }
+    }
+    start_locked(service, job).await
+}
+
+// Stop and start a running service so it picks up config changes. A stopped
+// service is left alone; returns whether a restart happened.
+pub(crate) async fn restart_if_running(service: &'static dyn Service) -> Result<bool, String> {
+    if tracked_pid(service.name())?.is_none() {
+        return Ok(false);
+    }
+    let label = format!("Restarting {}", service.display_name());
+    jobs::run("service-restart", label, |job| async move { restart(service, &job).await }).await
+}
+
+// Like restart_if_running, but in place for servers that can reload
+pub(crate) async fn reload_if_running(service: &'static dyn Service) -> Result<bool, String> {
+    {
+        let lock = service_lock(service.name());
+        let _guard = lock.lock().await;
+        let Some(pid) = tracked_pid(service.name())? else { return Ok(false) };
+        if service.reload(&get_project_root()?, pid).await? {
+            return Ok(true);
+        }
+    }
+    restart_if_running(service).await
+}
+
+#[tauri::command]
+pub async fn start_service(name: String) -> Result<bool, String> {
+    let service = find_service(&name)?;
+    supervisor::reset_crashes(service.name());
+    let label = format!("Starting {}", service.display_name());
+    jobs::run("service-start", label, |job| async move { start(service, &job).await }).await
+}
+
+#[tauri::command]
+pub async fn stop_service(name: String) -> Result<bool, String> {
+    stop(find_service(&name)?).await
+}
+
+// Start the service, stopping it first if it runs
+#[tauri::command]
+pub async fn restart_service(name: String) -> Result<bool, String> {
+    let service = find_service(&name)?;
+    supervisor::reset_crashes(service.name());
+    let label = format!("Restarting {}", service.display_name());
+    jobs::run("service-restart", label, |job| async move { restart(service, &job).await }).await
+}
+
+// Apply config changes to a running service, without dropping connections
+// where the server supports it
+#[tauri::command]
+pub async fn reload_service(name: String) -> Result<bool, String> {
+    let service = find_service(&name)?;
+    if reload_if_running(service).await? {
+        Ok(true)
+    } else {
+        Err(format!("{} is not running", service.display_name()))
+    }
+}
+
+#[tauri::command]
+pub async fn service_status(name: String) -> Result<ServiceInfo, String> {
+    status_of(find_service(&name)?).await
+}
+
+#[tauri::command]
+pub async fn list_services() -> Result<Vec<ServiceListing>, String> {
+    let base_path = get_project_root()?;
+    let mut listings = Vec::new();
+
+    for service in registry() {
+        listings.push(ServiceListing {
+            name: service.name().to_string(),
+            display_name: service.display_name().to_string(),
+            installed: service.binary(&base_path).is_some(),
+            info: status_of(service.as_ref()).await?,
+        });
+    }
+
+    Ok(listings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::fake::FakeRunner;
+    use crate::process::{Exit, Output};
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::TcpListener;
+    use tokio::task::JoinHandle;
+
+    // The runner, the status maps and the test root are process-wide
+    static SERIAL: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+    struct Fixture {
+        base_path: PathBuf,
+        runner: Arc<FakeRunner>,
+        port: u16,
+    }
+
+    impl Fixture {
+        // A DevStackBox root with a (never executed) mysqld and a free port
+        fn new(name: &str, initialized: bool, startup_timeout_secs: u64) -> Fixture {
+            let base_path = std::env::temp_dir().join(format!("devstackbox-services-{}-{}", name, std::process::id()));
+            let _ = std::fs::remove_dir_all(&base_path);
+            let bin_dir = base_path.join("mysql").join("bin");
+            std::fs::create_dir_all(&bin_dir).unwrap();
+            for name in ["mysqld", "mysqladmin"] {
+                std::fs::write(bin_dir.join(platform::exe_name(name)), "").unwrap();
+            }
+
+            let port = std::net::TcpListener::bind(("127.0.0.1", 0)).unwrap().local_addr().unwrap().port();
+            let mut settings = Settings::default();
+            settings.mysql.service.port = port;
+            settings.mysql.service.startup_timeout_secs = startup_timeout_secs;
+            settings::save(&base_path, &settings).unwrap();
+            if initialized {
+                std::fs::create_dir_all(settings.mysql_data_dir(&base_path).join("mysql")).unwrap();
+            }
+
+            crate::set_test_root(Some(base_path.clone()));
+            let runner = Arc::new(FakeRunner::new());
+            process::set_runner(runner.clone());
+            set_running(MySql.name(), None).unwrap();
+            Fixture { base_path, runner, port }
+        }
+
+        fn servers(&self) -> Vec<Cmd> {
+            self.runner.calls().into_iter().filter(is_server).collect()
+        }
+
+        fn finish(self) {
+            crate::set_test_root(None);
+            let _ = std::fs::remove_dir_all(&self.base_path);
+        }
+    }
+
+    fn is_server(cmd: &Cmd) -> bool {
+        !cmd.has_arg("--initialize-insecure")
+    }
+
+    // Answer like mysqld on the port once the server has been spawned
+    fn serve_handshake(runner: Arc<FakeRunner>, port: u16) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            while !runner.calls().iter().any(is_server) {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            let listener = TcpListener::bind(("127.0.0.1", port)).await.unwrap();
+            while let Ok((mut stream, _)) = listener.accept().await {
+                // Protocol version 10 and the NUL-terminated server version
+                let _ = stream.write_all(&[7, 0, 0, 0, 10, b'8', b'.', b'0', b'.', b'0', 0]).await;
+            }
+        })
+    }
+
+    async fn start_mysql() -> Result<bool, String> {
+        jobs::run("service-start", "Starting MySQL".to_string(), |job| async move { start(&MySql, &job).await }).await
+    }
+
+    #[tokio::test]
+    async fn start_initializes_the_data_directory_and_records_the_server() {
+        let _serial = SERIAL.lock().await;
+        let fixture = Fixture::new("start", false, 10);
+        fixture.runner.respond(|cmd| cmd.has_arg("--initialize-insecure"), Ok(Output::ok("")));
+        fixture.runner.set_server_output("ready for connections\n");
+        fixture.runner.set_server_pid(std::process::id());
+        let listener = serve_handshake(fixture.runner.clone(), fixture.port);
+
+        assert_eq!(start_mysql().await, Ok(true));
+        assert_eq!(tracked_pid("mysql"), Ok(Some(std::process::id())));
+
+        let calls = fixture.runner.calls();
+        assert_eq!(calls.len(), 2);
+        let data_dir = fixture.base_path.join("mysql").join("data");
+        assert!(calls[0].has_arg(&format!("--datadir={}", data_dir.display())));
+        assert!(is_server(&calls[1]));
+
+        // The console output goes to the captured log
+        let console_log = logs::console_log(&fixture.base_path, "mysql");
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while std::fs::read_to_string(&console_log).unwrap_or_default().is_empty() && Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(std::fs::read_to_string(&console_log).unwrap(), "ready for connections\n");
+
+        // Let the server go without stop(), which would signal this process
+        supervisor::expect_exit("mysql", true);
+        fixture.runner.exit(std::process::id(), Exit { code: Some(0), signal: None });
+        set_running("mysql", None).unwrap();
+        listener.abort();
+        fixture.finish();
+    }
+
+    #[tokio::test]
+    async fn start_gives_up_when_the_server_never_answers() {
+        let _serial = SERIAL.lock().await;
+        let fixture = Fixture::new("timeout", true, 1);
+
+        let error = start_mysql().await.unwrap_err();
+        assert!(error.starts_with("MySQL failed to start: not ready after 1s"), "{}", error);
+        assert_eq!(fixture.servers().len(), 1);
+        assert_eq!(tracked_pid("mysql"), Ok(None));
+        fixture.finish();
+    }
+
+    #[tokio::test]
+    async fn failed_initialization_does_not_start_the_server() {
+        let _serial = SERIAL.lock().await;
+        let fixture = Fixture::new("initialize", false, 10);
+        fixture.runner.respond(
+            |cmd| cmd.has_arg("--initialize-insecure"),
+            Ok(Output::failed(1, "mysqld: Can't create directory\n")),
+        );
+
+        let error = start_mysql().await.unwrap_err();
+        assert_eq!(
+            error,
+            "MySQL data directory initialization failed with exit code 1: mysqld: Can't create directory"
+        );
+        assert!(fixture.servers().is_empty());
+        assert!(!fixture.base_path.join("mysql").join("data").exists());
+        assert_eq!(tracked_pid("mysql"), Ok(None));
+        fixture.finish();
+    }
+
+    #[tokio::test]
+    async fn stop_waits_for_the_server_and_clears_it() {
+        let _serial = SERIAL.lock().await;
+        let fixture = Fixture::new("stop", true, 10);
+        assert_eq!(stop(&MySql).await, Err("MySQL is not running".to_string()));
+
+        // A fake PID no process has, so the server counts as gone once asked to stop
+        let child = process::runner().spawn(&Cmd::new("mysqld")).unwrap();
+        let pid = child.id();
+        set_running("mysql", Some(pid)).unwrap();
+        supervisor::watch("mysql", child);
+
+        assert_eq!(stop(&MySql).await, Ok(true));
+        assert_eq!(tracked_pid("mysql"), Ok(None));
+        fixture.runner.exit(pid, Exit { code: Some(0), signal: None });
+        fixture.finish();
+    }
+}
@@ -0,0 +1,389 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use super::{rewrite_lines, version_after, Probe, Service, ServiceContext};
+use crate::process::{self, BoxFuture, Cmd};
+use crate::{backups, logs, platform};
+use crate::settings::{self, ServiceSettings, Settings};
+use crate::templates::Vars;
+
+pub(crate) struct MySql;
+
+// --initialize creates the system tables, slow disks take a few minutes
+const INITIALIZE_TIMEOUT: Duration = Duration::from_secs(600);
+
+// mysqladmin only asks, stop() waits for the server to finish shutting down
+const SHUTDOWN_REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+impl Service for MySql {
+    fn name(&self) -> &'static str {
+        "mysql"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "MySQL"
+    }
+
+    fn bundled_dir(&self, base_path: &Path) -> PathBuf {
+        base_path.join("mysql")
+    }
+
+    fn binary_names(&self) -> &'static [&'static str] {
+        &["mysqld"]
+    }
+
+    fn config_path(&self, base_path: &Path) -> PathBuf {
+        base_path.join("config").join("my.cnf")
+    }
+
+    fn config_template(&self) -> &'static str {
+        include_str!("../../templates/my.cnf")
+    }
+
+    fn template_vars(&self, base_path: &Path, settings: &Settings) -> Vars {
+        let mysql = &settings.mysql;
+        let mysql_base = base_path.join("mysql");
+
+        // On Unix the default socket lives in a root-owned directory, keep ours local
+        let socket = if cfg!(unix) {
+            format!("socket={}", platform::config_path(&mysql_base.join("mysql.sock")))
+        } else {
+            String::new()
+        };
+
+        let base_dir = self.binary(base_path).map(|binary| install_dir(&binary)).unwrap_or(mysql_base);
+        Vars::from([
+            ("base_dir", platform::config_path(&base_dir)),
+            ("data_dir", platform::config_path(&settings.mysql_data_dir(base_path))),
+            ("socket", socket),
+            ("pid_file", platform::config_path(&settings.mysql_data_dir(base_path).join("mysqld.pid"))),
+            ("sql_mode", mysql.sql_mode.clone()),
+            ("max_connections", mysql.max_connections.to_string()),
+            ("bind_address", mysql.bind_address.clone()),
+            ("character_set", mysql.character_set.clone()),
+        ])
+    }
+
+    fn service_settings<'a>(&self, settings: &'a Settings) -> &'a ServiceSettings {
+        &settings.mysql.service
+    }
+
+    fn service_settings_mut<'a>(&self, settings: &'a mut Settings) -> &'a mut ServiceSettings {
+        &mut settings.mysql.service
+    }
+
+    fn apply_settings(&self, base_path: &Path, previous: &Settings, current: &Settings) -> Result<(), String> {
+        let config_path = self.config_path(base_path);
+        if !config_path.exists() {
+            return Ok(());
+        }
+
+        let (old, new) = (&previous.mysql, &current.mysql);
+        let mut changes: Vec<(&str, &str, String)> = Vec::new();
+        if old.bind_address != new.bind_address {
+            changes.push(("mysqld", "bind-address", new.bind_address.clone()));
+        }
+        if old.max_connections != new.max_connections {
+            changes.push(("mysqld", "max_connections", new.max_connections.to_string()));
+        }
+        if old.sql_mode != new.sql_mode {
+            changes.push(("mysqld", "sql-mode", format!("\"{}\"", new.sql_mode)));
+        }
+        if previous.paths.mysql_data_dir != current.paths.mysql_data_dir {
+            let data_dir = current.mysql_data_dir(base_path);
+            changes.push(("mysqld", "datadir", platform::config_path(&data_dir)));
+            changes.push(("mysqld", "pid-file", platform::config_path(&data_dir.join("mysqld.pid"))));
+        }
+        if old.character_set != new.character_set {
+            changes.push(("mysql", "default-character-set", new.character_set.clone()));
+            changes.push(("client", "default-character-set", new.character_set.clone()));
+        }
+        if changes.is_empty() {
+            return Ok(());
+        }
+
+        let mut content = std::fs::read_to_string(&config_path).map_err(|e| e.to_string())?;
+        for (section, key, value) in changes {
+            content = set_ini_key(&content, section, key, &value);
+        }
+        backups::write_config_file(base_path, &config_path, &content, "Settings updated")
+    }
+
+    fn default_port(&self) -> u16 {
+        3306
+    }
+
+    fn parse_port(&self, config: &str) -> Option<u16> {
+        // First "port=" in the [mysqld] section
+        let mut in_mysqld = false;
+        for line in config.lines() {
+            let line = line.trim();
+            if line.starts_with('[') {
+                in_mysqld = line == "[mysqld]";
+            } else if in_mysqld {
+                if let Some(value) = ini_value(line, "port") {
+                    return value.parse().ok();
+                }
+            }
+        }
+        None
+    }
+
+    fn rewrite_port(&self, config: &str, port: u16) -> String {
+        // Server and client sections have to agree
+        rewrite_lines(config, |line| {
+            ini_value(line, "port").map(|_| {
+                let indent = &line[..line.len() - line.trim_start().len()];
+                format!("{}port={}", indent, port)
+            })
+        })
+    }
+
+    fn apply_port_to_clients(&self, base_path: &Path, port: u16) -> Result<(), String> {
+        // phpMyAdmin only needs updating if it has been configured
+        let config_path = base_path.join("phpmyadmin").join("config.inc.php");
+        if !config_path.exists() {
+            return Ok(());
+        }
+
+        let content = std::fs::read_to_string(&config_path).map_err(|e| e.to_string())?;
+        backups::write_config_file(
+            base_path,
+            &config_path,
+            &phpmyadmin_with_port(&content, port),
+            &format!("MySQL port changed to {}", port),
+        )
+    }
+
+    fn prepare<'a>(&'a self, ctx: &'a ServiceContext) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            let data_dir = settings::load(&ctx.base_path)?.mysql_data_dir(&ctx.base_path);
+
+            // Check if data directory is empty or missing mysql system tables
+            if data_dir.join("mysql").exists() {
+                return Ok(());
+            }
+
+            // Initialize MySQL data directory
+            ctx.job.phase("Initializing data directory");
+            let created = !data_dir.exists();
+            let cmd = Cmd::new(&ctx.binary)
+                .arg("--initialize-insecure")
+                .arg(format!("--basedir={}", install_dir(&ctx.binary).display()))
+                .arg(format!("--datadir={}", data_dir.display()));
+
+            // Initialization can take a minute on slow disks, keep it cancellable
+            let runner = process::runner();
+            let result = match ctx.job.cancellable(runner.output(&cmd, INITIALIZE_TIMEOUT)).await {
+                Ok(output) if output.success() => {
+                    println!("MySQL data directory initialized successfully");
+                    return Ok(());
+                }
+                Ok(output) => Err(format!(
+                    "MySQL data directory initialization failed with {}: {}",
+                    output.code.map(|code| format!("exit code {}", code)).unwrap_or_else(|| "a signal".to_string()),
+                    output.stderr.trim()
+                )),
+                Err(e) if e == "Cancelled" => Err(e),
+                Err(e) => Err(format!("Failed to initialize MySQL data directory: {}", e)),
+            };
+
+            // A half-initialized directory would make the next --initialize fail
+            if created {
+                let _ = std::fs::remove_dir_all(&data_dir);
+            }
+            result
+        })
+    }
+
+    fn start_command(&self, ctx: &ServiceContext) -> Cmd {
+        let command = Cmd::new(&ctx.binary).arg(format!("--defaults-file={}", ctx.config_path.display()));
+        if cfg!(windows) {
+            // Log to the console instead of the Windows event log
+            return command.arg("--console");
+        }
+        command
+    }
+
+    fn probe(&self) -> Probe {
+        Probe::MySqlHandshake
+    }
+
+    // mysqld shuts down cleanly on SIGTERM. Windows has no signals, so there
+    // the shutdown is requested over the client protocol.
+    fn request_stop<'a>(&'a self, base_path: &'a Path, pid: u32) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            if cfg!(unix) {
+                return process::blocking(move || platform::stop_process(pid)).await;
+            }
+            let mysqladmin = platform::resolve_binary(&self.bundled_dir(base_path), &["mysqladmin"])
+                .ok_or("mysqladmin not found")?;
+            let cmd = Cmd::new(&mysqladmin)
+                .arg(format!("--defaults-file={}", self.config_path(base_path).display()))
+                .args(["--user=root", "--connect-timeout=5", "shutdown"]);
+            let output = process::runner().output(&cmd, SHUTDOWN_REQUEST_TIMEOUT).await?;
+            if output.success() {
+                Ok(())
+            } else {
+                Err(output.stderr.trim().to_string())
+            }
+        })
+    }
+
+    fn log_files(&self, base_path: &Path) -> Vec<(&'static str, PathBuf)> {
+        let config = std::fs::read_to_string(self.config_path(base_path)).unwrap_or_default();
+        let error_log = mysqld_value(&config, "log-error")
+            .filter(|value| !value.is_empty() && *value != "stderr")
+            .and_then(|value| {
+                // Relative names go in the data directory, ".err" is added without an extension
+                let data_dir = settings::load(base_path).ok()?.mysql_data_dir(base_path);
+                let mut path = data_dir.join(value);
+                if path.extension().is_none() {
+                    path.set_extension("err");
+                }
+                Some(path)
+            });
+        // Without log-error mysqld logs to the console we capture
+        vec![("error", error_log.unwrap_or_else(|| logs::console_log(base_path, self.name())))]
+    }
+
+    // SIGHUP flushes the logs, reopening log-error
+    fn reopen_signal(&self) -> Option<platform::ReopenSignal> {
+        Some(platform::ReopenSignal::Hangup)
+    }
+
+    fn pid_file(&self, base_path: &Path) -> Option<PathBuf> {
+        let config = std::fs::read_to_string(self.config_path(base_path)).unwrap_or_default();
+        let data_dir = settings::load(base_path).ok()?.mysql_data_dir(base_path);
+        if let Some(value) = mysqld_value(&config, "pid-file").filter(|value| !value.is_empty()) {
+            let mut path = data_dir.join(value);
+            if path.extension().is_none() {
+                path.set_extension("pid");
+            }
+            return Some(path);
+        }
+        // Configs from before pid-file was set get mysqld's default, <hostname>.pid
+        std::fs::read_dir(&data_dir)
+            .ok()?
+            .flatten()
+            .map(|entry| entry.path())
+            .find(|path| path.extension().is_some_and(|extension| extension == "pid"))
+    }
+
+    fn version_args(&self) -> &'static [&'static str] {
+        &["--version"]
+    }
+
+    fn parse_version(&self, output: &str) -> Option<String> {
+        // Extract version from output like "mysqld  Ver 8.0.35 for Win64 on x86_64"
+        version_after(output, "Ver ")
+    }
+}
+
+// The installation mysqld belongs to, where it finds share/ and its errmsg
+// files: <root>/mysql for the bundled copy, /usr for /usr/sbin/mysqld
+fn install_dir(binary: &Path) -> PathBuf {
+    // Package managers (Homebrew, alternatives) link the binary into a shared
+    // bin directory; Windows would hand back a \\?\ path mysqld cannot use
+    let binary = if cfg!(unix) {
+        std::fs::canonicalize(binary).unwrap_or_else(|_| binary.to_path_buf())
+    } else {
+        binary.to_path_buf()
+    };
+    let bin_dir = binary.parent().unwrap_or(Path::new(""));
+    match bin_dir.file_name().and_then(|name| name.to_str()) {
+        Some("bin" | "sbin") => bin_dir.parent().unwrap_or(bin_dir).to_path_buf(),
+        _ => bin_dir.to_path_buf(),
+    }
+}
+
+// Value of `key` on an ini line ("port = 3306" -> "3306")
+fn ini_value<'a>(line: &'a str, key: &str) -> Option<&'a str> {
+    let (name, value) = line.split_once('=')?;
+    if name.trim() == key {
+        Some(value.trim().trim_matches('"'))
+    } else {
+        None
+    }
+}
+
+// Last value of `key` in [mysqld], with either '-' or '_' spelling
+fn mysqld_value<'a>(content: &'a str, key: &str) -> Option<&'a str> {
+    let mut in_mysqld = false;
+    let mut found = None;
+    for line in content.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_mysqld = line == "[mysqld]";
+        } else if in_mysqld {
+            found = ini_value(line, key).or_else(|| ini_value(line, &key.replace('-', "_"))).or(found);
+        }
+    }
+    found
+}
+
+// Set `key=value` inside `[section]`, replacing an existing assignment (with
+// either '-' or '_' spelling) or adding it right after the section header
+fn set_ini_key(content: &str, section: &str, key: &str, value: &str) -> String {
+    let normalize = |name: &str| name.trim().replace('_', "-");
+    let header = format!("[{}]", section);
+    let mut current_section = String::new();
+    let mut replaced = false;
+
+    let updated = rewrite_lines(content, |line| {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') {
+            current_section = trimmed.to_string();
+            return None;
+        }
+        if current_section != header {
+            return None;
+        }
+        let (name, _) = trimmed.split_once('=')?;
+        if normalize(name) == normalize(key) {
+            replaced = true;
+            Some(format!("{}={}", key, value))
+        } else {
+            None
+        }
+    });
+    if replaced {
+        return updated;
+    }
+
+    rewrite_lines(&updated, |line| {
+        (line.trim() == header).then(|| format!("{}\n{}={}", line, key, value))
+    })
+}
+
+const PHPMYADMIN_PORT_KEY: &str = "$cfg['Servers'][$i]['port']";
+
+// Point phpMyAdmin's server entry at the new port, adding the setting after
+// the host line when the config doesn't have one yet
+fn phpmyadmin_with_port(content: &str, port: u16) -> String {
+    let port_line = format!("{} = '{}';", PHPMYADMIN_PORT_KEY, port);
+
+    if content.contains(PHPMYADMIN_PORT_KEY) {
+        return rewrite_lines(content, |line| {
+            line.trim_start().starts_with(PHPMYADMIN_PORT_KEY).then(|| port_line.clone())
+        });
+    }
+
+    let mut inserted = false;
+    let mut lines = Vec::new();
+    for line in content.lines() {
+        if !inserted && line.trim() == "?>" {
+            lines.push(port_line.clone());
+            inserted = true;
+        }
+        lines.push(line.to_string());
+        if !inserted && line.trim_start().starts_with("$cfg['Servers'][$i]['host']") {
+            lines.push(port_line.clone());
+            inserted = true;
+        }
+    }
+    if !inserted {
+        lines.push(port_line);
+    }
+    lines.join("\n") + "\n"
+}
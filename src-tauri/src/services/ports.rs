@@ -0,0 +1,145 @@
+// Port pre-flight checks. Before starting a service we look at who holds its
+// port, and offer the next free port so the user can move the service instead
+// of fighting Skype, IIS or a system MySQL for it.
+
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::Path;
+use std::time::Duration;
+
+use super::{find_service, status_of, tracked_pid, Service, ServiceInfo};
+use crate::{backups, get_project_root, platform, process, settings};
+
+#[derive(serde::Serialize)]
+pub struct PortHolder {
+    pid: u32,
+    name: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+pub struct PortCheck {
+    service: String,
+    port: u16,
+    pub(crate) available: bool,
+    holders: Vec<PortHolder>,
+    suggested_port: Option<u16>,
+}
+
+impl PortCheck {
+    // "Port 80 is already in use by Skype.exe (PID 4242). Port 8080 is free and ..."
+    pub(crate) fn conflict_message(&self) -> String {
+        let holders = self
+            .holders
+            .iter()
+            .map(|holder| match &holder.name {
+                Some(name) => format!("{} (PID {})", name, holder.pid),
+                None => format!("PID {}", holder.pid),
+            })
+            .collect::<Vec<_>>();
+
+        let mut message = if holders.is_empty() {
+            format!("Port {} is already in use", self.port)
+        } else {
+            format!("Port {} is already in use by {}", self.port, holders.join(", "))
+        };
+        if let Some(suggested) = self.suggested_port {
+            message.push_str(&format!(". Port {} is free and can be used instead", suggested));
+        }
+        message
+    }
+}
+
+fn accepts_connections(port: u16) -> bool {
+    let addr = SocketAddr::from(([127, 0, 0, 1], port));
+    TcpStream::connect_timeout(&addr, Duration::from_millis(300)).is_ok()
+}
+
+fn port_in_use(port: u16) -> bool {
+    platform::listening_pids(port).is_some_and(|pids| !pids.is_empty()) || accepts_connections(port)
+}
+
+// A port is usable when nothing listens on it and we are allowed to bind it
+// (ports below 1024 need privileges on Unix)
+fn port_usable(port: u16) -> bool {
+    !port_in_use(port) && TcpListener::bind(("127.0.0.1", port)).is_ok()
+}
+
+// Well-known ports map to their customary alternative (80 -> 8080, 443 -> 8443),
+// everything else moves up by one
+pub(crate) fn suggest_port(port: u16) -> Option<u16> {
+    let start = if port < 1024 { 8000 + port } else { port.checked_add(1)? };
+    (start..=start.saturating_add(100)).find(|&candidate| port_usable(candidate))
+}
+
+pub(crate) async fn check_port(service: &dyn Service, base_path: &Path) -> PortCheck {
+    let name = service.name().to_string();
+    let port = service.port(base_path);
+    process::blocking(move || check(name, port)).await
+}
+
+// Asks the OS who listens and tries to connect, both of which block
+fn check(service: String, port: u16) -> PortCheck {
+    let pids = platform::listening_pids(port).unwrap_or_default();
+    let available = pids.is_empty() && !accepts_connections(port);
+
+    PortCheck {
+        service,
+        port,
+        available,
+        holders: pids
+            .into_iter()
+            .map(|pid| PortHolder { pid, name: platform::process_name(pid) })
+            .collect(),
+        suggested_port: if available { None } else { suggest_port(port) },
+    }
+}
+
+// Move a service to a new port: its own config file, anything that points at
+// it (phpMyAdmin for MySQL), and therefore the port reported in ServiceInfo
+pub(crate) fn apply_port(service: &dyn Service, base_path: &Path, port: u16) -> Result<(), String> {
+    let config_path = service.config_path(base_path);
+    if !config_path.exists() {
+        super::config::write_config(service, base_path, "Generated default config")?;
+    }
+
+    let content = std::fs::read_to_string(&config_path).map_err(|e| e.to_string())?;
+    let updated = service.rewrite_port(&content, port);
+    backups::write_config_file(base_path, &config_path, &updated, &format!("Port changed to {}", port))?;
+
+    service.apply_port_to_clients(base_path, port)
+}
+
+#[tauri::command]
+pub async fn check_service_port(name: String) -> Result<PortCheck, String> {
+    let base_path = get_project_root()?;
+    Ok(check_port(find_service(&name)?, &base_path).await)
+}
+
+#[tauri::command]
+pub async fn check_service_ports() -> Result<Vec<PortCheck>, String> {
+    let base_path = get_project_root()?;
+    let mut checks = Vec::new();
+    for service in super::registry() {
+        // A running service holding its own port is not a conflict
+        if tracked_pid(service.name())?.is_none() {
+            checks.push(check_port(service.as_ref(), &base_path).await);
+        }
+    }
+    Ok(checks)
+}
+
+#[tauri::command]
+pub async fn set_service_port(name: String, port: u16) -> Result<ServiceInfo, String> {
+    let service = find_service(&name)?;
+    if tracked_pid(service.name())?.is_some() {
+        return Err(format!("Stop {} before changing its port", service.display_name()));
+    }
+
+    let base_path = get_project_root()?;
+    let mut settings = settings::load(&base_path)?;
+    service.service_settings_mut(&mut settings).port = port;
+    settings.validate()?;
+    settings::save(&base_path, &settings)?;
+
+    apply_port(service, &base_path, port)?;
+    status_of(service).await
+}
@@ -0,0 +1,193 @@
+// Readiness probes. After spawning a server we poll it with backoff until it
+// answers its own protocol, then confirm the socket belongs to the process we
+// spawned rather than to whatever else happens to be on the port.
+
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::{sleep, timeout};
+
+use super::Service;
+use crate::jobs::JobHandle;
+use crate::platform;
+use crate::process::{self, Process};
+
+// How a service proves it is up
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Probe {
+    // Plain TCP connect
+    Tcp,
+    // Read the MySQL initial handshake packet
+    MySqlHandshake,
+    // Send an HTTP GET and accept any status line
+    Http { path: &'static str },
+}
+
+// Overall timeout plus exponential backoff between attempts
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Readiness {
+    pub timeout: Duration,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for Readiness {
+    fn default() -> Self {
+        Readiness {
+            timeout: Duration::from_secs(30),
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(2),
+        }
+    }
+}
+
+// Per-attempt limit so a half-open socket cannot stall the whole wait
+const ATTEMPT_TIMEOUT: Duration = Duration::from_secs(2);
+
+pub(crate) async fn run_probe(probe: Probe, port: u16) -> Result<(), String> {
+    match timeout(ATTEMPT_TIMEOUT, probe_once(probe, port)).await {
+        Ok(result) => result,
+        Err(_) => Err(format!("no response on port {} within {:?}", port, ATTEMPT_TIMEOUT)),
+    }
+}
+
+async fn probe_once(probe: Probe, port: u16) -> Result<(), String> {
+    let mut stream = TcpStream::connect(("127.0.0.1", port))
+        .await
+        .map_err(|e| format!("connect to port {} failed: {}", port, e))?;
+
+    match probe {
+        Probe::Tcp => Ok(()),
+        Probe::MySqlHandshake => mysql_handshake(&mut stream).await.map(|_| ()),
+        Probe::Http { path } => http_status(&mut stream, port, path).await.map(|_| ()),
+    }
+}
+
+// Returns the server version from the handshake, or None when the server
+// answered with an error packet (e.g. "Host is not allowed to connect"),
+// which still proves mysqld is up.
+async fn mysql_handshake(stream: &mut TcpStream) -> Result<Option<String>, String> {
+    let mut header = [0u8; 4];
+    stream
+        .read_exact(&mut header)
+        .await
+        .map_err(|e| format!("no MySQL handshake: {}", e))?;
+    let length = u32::from_le_bytes([header[0], header[1], header[2], 0]) as usize;
+    if length == 0 {
+        return Err("empty MySQL handshake packet".to_string());
+    }
+
+    let mut payload = vec![0u8; length];
+    stream
+        .read_exact(&mut payload)
+        .await
+        .map_err(|e| format!("truncated MySQL handshake: {}", e))?;
+
+    match payload[0] {
+        // Protocol version 10, followed by the NUL-terminated server version
+        10 => {
+            let version = payload[1..].split(|&byte| byte == 0).next().unwrap_or_default();
+            Ok(Some(String::from_utf8_lossy(version).to_string()))
+        }
+        0xff => Ok(None),
+        other => Err(format!("unexpected MySQL protocol version {}", other)),
+    }
+}
+
+async fn http_status(stream: &mut TcpStream, port: u16, path: &str) -> Result<u16, String> {
+    let request = format!(
+        "GET {} HTTP/1.0\r\nHost: localhost:{}\r\nUser-Agent: DevStackBox\r\nConnection: close\r\n\r\n",
+        path, port
+    );
+    stream
+        .write_all(request.as_bytes())
+        .await
+        .map_err(|e| format!("HTTP request failed: {}", e))?;
+
+    // The status line fits comfortably in the first read
+    let mut buffer = [0u8; 256];
+    let read = stream
+        .read(&mut buffer)
+        .await
+        .map_err(|e| format!("HTTP response failed: {}", e))?;
+    parse_status_line(&String::from_utf8_lossy(&buffer[..read]))
+}
+
+fn parse_status_line(response: &str) -> Result<u16, String> {
+    // "HTTP/1.1 200 OK"
+    let line = response.lines().next().unwrap_or_default();
+    let mut parts = line.split_whitespace();
+    match (parts.next(), parts.next().and_then(|code| code.parse::<u16>().ok())) {
+        (Some(protocol), Some(code)) if protocol.starts_with("HTTP/") => Ok(code),
+        _ => Err(format!("not an HTTP response: {:?}", line)),
+    }
+}
+
+// The socket must be held by the spawned process or one of its children
+// (Apache hands listeners to worker processes).
+fn verify_owner(pid: u32, port: u16) -> Result<(), String> {
+    let owners = match platform::listening_pids(port) {
+        Some(owners) => owners,
+        // Ownership cannot be checked on this system, trust the probe
+        None => return Ok(()),
+    };
+
+    let ours = owners
+        .iter()
+        .any(|&owner| owner == pid || platform::parent_pid(owner) == Some(pid));
+    if ours {
+        Ok(())
+    } else if owners.is_empty() {
+        Err(format!("port {} is held by a process outside our control", port))
+    } else {
+        Err(format!(
+            "port {} is held by another process (PID {})",
+            port,
+            owners.iter().map(|owner| owner.to_string()).collect::<Vec<_>>().join(", ")
+        ))
+    }
+}
+
+// Poll until the service answers and owns its port, the child exits, the
+// readiness timeout expires or the job is cancelled
+pub(crate) async fn wait_until_ready(
+    service: &dyn Service,
+    child: &mut dyn Process,
+    port: u16,
+    readiness: Readiness,
+    job: &JobHandle,
+) -> Result<(), String> {
+    let pid = child.id();
+    let deadline = Instant::now() + readiness.timeout;
+    let mut backoff = readiness.initial_backoff;
+
+    loop {
+        job.check_cancelled()?;
+        if let Ok(Some(exit)) = child.try_wait() {
+            return Err(format!("process exited with {} before it was ready", exit));
+        }
+
+        // The owner lookup can briefly miss the socket (a worker that is still
+        // being forked holds it), so a failed check is retried like a probe
+        let last_error = match run_probe(service.probe(), port).await {
+            Ok(()) => match process::blocking(move || verify_owner(pid, port)).await {
+                Ok(()) => return Ok(()),
+                Err(e) => e,
+            },
+            Err(e) => e,
+        };
+
+        if Instant::now() + backoff > deadline {
+            return Err(format!(
+                "not ready after {}s ({})",
+                readiness.timeout.as_secs(),
+                last_error
+            ));
+        }
+        tokio::select! {
+            _ = sleep(backoff) => {}
+            _ = job.cancelled() => return Err("Cancelled".to_string()),
+        }
+        backoff = (backoff * 2).min(readiness.max_backoff);
+    }
+}
@@ -0,0 +1,110 @@
+// Start profiles: named sets of services brought up together from the
+// [profiles.<name>] settings. Services start in dependency order, each one
+// only after those it depends on pass their readiness check; dependencies the
+// profile does not list are started as well. Stopping goes in reverse.
+
+use super::{find_service, start, stop, supervisor, tracked_pid, Service};
+use crate::settings::{self, ProfileSettings};
+use crate::{get_project_root, jobs};
+
+// The profile's services with every dependency ahead of its dependents,
+// otherwise in the order they are listed
+pub(crate) fn start_order(name: &str, profile: &ProfileSettings) -> Result<Vec<&'static dyn Service>, String> {
+    if profile.services.is_empty() {
+        return Err(format!("Profile '{}' has no services", name));
+    }
+    let mut order = Vec::new();
+    for service in &profile.services {
+        visit(name, profile, service, &mut Vec::new(), &mut order)?;
+    }
+    Ok(order)
+}
+
+fn visit(
+    name: &str,
+    profile: &ProfileSettings,
+    service: &str,
+    path: &mut Vec<&'static str>,
+    order: &mut Vec<&'static dyn Service>,
+) -> Result<(), String> {
+    let service = find_service(service).map_err(|e| format!("Profile '{}': {}", name, e))?;
+    if order.iter().any(|ordered| ordered.name() == service.name()) {
+        return Ok(());
+    }
+    if path.contains(&service.name()) {
+        path.push(service.name());
+        return Err(format!("Profile '{}' has a dependency cycle: {}", name, path.join(" -> ")));
+    }
+
+    path.push(service.name());
+    for dependency in profile.depends_on.get(service.name()).into_iter().flatten() {
+        visit(name, profile, dependency, path, order)?;
+    }
+    path.pop();
+    order.push(service);
+    Ok(())
+}
+
+fn load_profile(name: &str) -> Result<Vec<&'static dyn Service>, String> {
+    let settings = settings::load(&get_project_root()?)?;
+    let profile = settings
+        .profiles
+        .get(name)
+        .ok_or_else(|| format!("Unknown profile: {}", name))?;
+    start_order(name, profile)
+}
+
+// Start the profile's services that are not running yet; returns those started
+async fn start_services(name: &str) -> Result<Vec<String>, String> {
+    let services = load_profile(name)?;
+    let label = format!("Starting profile {}", name);
+    jobs::run("profile-start", label, |job| async move {
+        let mut started = Vec::new();
+        for service in services {
+            if tracked_pid(service.name())?.is_some() {
+                continue;
+            }
+            job.phase(&format!("Starting {}", service.display_name()));
+            supervisor::reset_crashes(service.name());
+            start(service, &job).await?;
+            started.push(service.name().to_string());
+        }
+        Ok(started)
+    })
+    .await
+}
+
+// Launch the profile named in app.auto_start_profile, called once from setup
+pub(crate) async fn auto_start() {
+    let Ok(base_path) = get_project_root() else { return };
+    let Some(name) = settings::load(&base_path).ok().and_then(|settings| settings.app.auto_start_profile) else { return };
+    if let Err(e) = start_services(&name).await {
+        println!("Failed to start profile {}: {}", name, e);
+    }
+}
+
+#[tauri::command]
+pub async fn start_profile(name: String) -> Result<Vec<String>, String> {
+    start_services(&name).await
+}
+
+// Stop the profile's running services, dependents first; returns those stopped
+#[tauri::command]
+pub async fn stop_profile(name: String) -> Result<Vec<String>, String> {
+    let mut stopped = Vec::new();
+    let mut errors = Vec::new();
+    for service in load_profile(&name)?.into_iter().rev() {
+        if tracked_pid(service.name())?.is_none() {
+            continue;
+        }
+        match stop(service).await {
+            Ok(_) => stopped.push(service.name().to_string()),
+            Err(e) => errors.push(e),
+        }
+    }
+    if errors.is_empty() {
+        Ok(stopped)
+    } else {
+        Err(errors.join("; "))
+    }
+}
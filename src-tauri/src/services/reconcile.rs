@@ -0,0 +1,81 @@
+// Startup reconciliation. The status maps only live as long as the app, the
+// servers it started do not: after the app restarts (or crashes) mysqld and
+// httpd may still be running and holding their ports. Their pid files say
+// where they are. A PID that is alive and runs our binary is adopted as the
+// running service; anything else means the pid file is stale and it is removed.
+
+use std::path::Path;
+
+use super::{registry, set_running, supervisor, tracked_pid, Service};
+use crate::{get_project_root, platform, process};
+
+// Called once from setup, inside the async runtime supervision runs on
+pub(crate) async fn reconcile() {
+    let Ok(base_path) = get_project_root() else { return };
+    for service in registry() {
+        match reconcile_service(service.as_ref(), &base_path).await {
+            Ok(Some(pid)) => println!("{} is already running (PID {}), adopted it", service.display_name(), pid),
+            Ok(None) => {}
+            Err(e) => println!("Failed to check for a running {}: {}", service.display_name(), e),
+        }
+    }
+}
+
+// Adopt the service's running server; returns its PID
+async fn reconcile_service(service: &'static dyn Service, base_path: &Path) -> Result<Option<u32>, String> {
+    if tracked_pid(service.name())?.is_some() {
+        return Ok(None);
+    }
+    let Some(pid_file) = service.pid_file(base_path) else { return Ok(None) };
+    let content = match std::fs::read_to_string(&pid_file) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Failed to read {}: {}", pid_file.display(), e)),
+    };
+
+    let pid = content.trim().parse::<u32>().ok();
+    let owned_base_path = base_path.to_path_buf();
+    // The process lookups shell out on Windows and macOS
+    let ours = match pid {
+        Some(pid) => process::blocking(move || is_ours(service, &owned_base_path, pid)).await,
+        None => false,
+    };
+    match pid {
+        Some(pid) if ours => {
+            set_running(service.name(), Some(pid))?;
+            supervisor::adopt(service.name(), pid);
+            Ok(Some(pid))
+        }
+        _ => {
+            println!("Removing stale {}", pid_file.display());
+            std::fs::remove_file(&pid_file).map_err(|e| format!("Failed to remove {}: {}", pid_file.display(), e))?;
+            Ok(None)
+        }
+    }
+}
+
+// The PID is alive and runs the service's binary, not some program that got
+// the number after our server went away
+pub(super) fn is_ours(service: &dyn Service, base_path: &Path, pid: u32) -> bool {
+    if !platform::process_alive(pid) {
+        return false;
+    }
+    let Some(binary) = service.binary(base_path) else { return false };
+    match platform::process_path(pid) {
+        Some(path) => same_file(&path, &binary),
+        // Processes we may not inspect fully are judged by their name
+        None => platform::process_name(pid).is_some_and(|name| {
+            service
+                .binary_names()
+                .iter()
+                .any(|binary_name| name.eq_ignore_ascii_case(&platform::exe_name(binary_name)))
+        }),
+    }
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (std::fs::canonicalize(a), std::fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
@@ -0,0 +1,243 @@
+// Crash supervision. Every server start() brings up is handed to watch(),
+// which waits on the process from a task of its own. An exit nobody asked
+// for marks the service stopped and emits "service-crashed" with the exit
+// status and the end of the service's error log. With auto_restart on, the
+// service is started again after an exponential backoff, until it has crashed
+// MAX_CRASHES times within CRASH_WINDOW.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+use tokio::runtime::Handle;
+
+use super::{find_service, reconcile, set_running, start, tracked_pid};
+use crate::process::{self, Exit, Process};
+use crate::{get_project_root, jobs, logs, platform, settings};
+
+pub const CRASH_EVENT: &str = "service-crashed";
+
+pub(crate) const MAX_CRASHES: usize = 5;
+pub(crate) const CRASH_WINDOW: Duration = Duration::from_secs(10 * 60);
+
+// Doubled after every crash in the window
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+pub(crate) const CRASH_LOG_LINES: usize = 20;
+
+// How often an adopted server is checked for
+const ADOPTED_POLL: Duration = Duration::from_secs(2);
+
+static SUPERVISED: LazyLock<Mutex<HashMap<String, Supervised>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Default)]
+struct Supervised {
+    // The process being watched
+    pid: Option<u32>,
+    // stop() is taking it down, so its exit is not a crash
+    stopping: bool,
+    // A restart is waiting out its backoff
+    restart_pending: bool,
+    // Recent crashes, within CRASH_WINDOW
+    crashes: Vec<Instant>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct ServiceCrash {
+    pub service: String,
+    // None when a restart attempt failed before the server came up
+    pub pid: Option<u32>,
+    pub exit_code: Option<i32>,
+    // Unix signal that ended the process
+    pub signal: Option<i32>,
+    // Why the restart attempt failed
+    pub error: Option<String>,
+    pub last_lines: Vec<String>,
+    // Crashes within the crash window, this one included
+    pub crashes: usize,
+    // Delay before the next start attempt, None when none follows
+    pub restart_in_secs: Option<u64>,
+    // auto_restart is on but the service crashed too often in a row
+    pub gave_up: bool,
+}
+
+impl ServiceCrash {
+    pub(crate) fn new(service: &str, pid: Option<u32>) -> ServiceCrash {
+        ServiceCrash {
+            service: service.to_string(),
+            pid,
+            exit_code: None,
+            signal: None,
+            error: None,
+            last_lines: Vec::new(),
+            crashes: 0,
+            restart_in_secs: None,
+            gave_up: false,
+        }
+    }
+}
+
+// Watch a server that has just come up. Must be called from the async runtime,
+// which later runs the restarts.
+pub(crate) fn watch(name: &'static str, mut child: Box<dyn Process>) {
+    let pid = child.id();
+    track(name, pid);
+
+    let runtime = Handle::current();
+    runtime.clone().spawn(async move {
+        let exit = child.wait().await.ok();
+        exited(name, pid, exit, &runtime);
+    });
+}
+
+// Watch a server started by an earlier run of the app. It is not our child, so
+// there is no exit status to wait for; the PID is polled instead, with the
+// check reconcile adopted it by, so a reused PID counts as an exit. Must be
+// called from the async runtime.
+pub(crate) fn adopt(name: &'static str, pid: u32) {
+    track(name, pid);
+
+    let runtime = Handle::current();
+    runtime.clone().spawn(async move {
+        loop {
+            tokio::time::sleep(ADOPTED_POLL).await;
+            // Its exit has been seen to already, or a new start replaced it
+            if !watching(name, pid) {
+                return;
+            }
+            if !process::blocking(move || still_ours(name, pid)).await {
+                break;
+            }
+        }
+        exited(name, pid, None, &runtime);
+    });
+}
+
+fn watching(name: &str, pid: u32) -> bool {
+    SUPERVISED
+        .lock()
+        .is_ok_and(|supervised| supervised.get(name).is_some_and(|entry| entry.pid == Some(pid)))
+}
+
+fn still_ours(name: &str, pid: u32) -> bool {
+    match (find_service(name), get_project_root()) {
+        (Ok(service), Ok(base_path)) => reconcile::is_ours(service, &base_path, pid),
+        _ => platform::process_alive(pid),
+    }
+}
+
+fn track(name: &str, pid: u32) {
+    if let Ok(mut supervised) = SUPERVISED.lock() {
+        let entry = supervised.entry(name.to_string()).or_default();
+        entry.pid = Some(pid);
+        entry.stopping = false;
+        entry.restart_pending = false;
+    }
+}
+
+// stop() is about to take the service down (or failed to)
+pub(crate) fn expect_exit(name: &str, expected: bool) {
+    if let Ok(mut supervised) = SUPERVISED.lock() {
+        if let Some(entry) = supervised.get_mut(name) {
+            entry.stopping = expected;
+        }
+    }
+}
+
+// Drop a restart that is waiting out its backoff; returns whether there was one
+pub(crate) fn cancel_restart(name: &str) -> bool {
+    match SUPERVISED.lock() {
+        Ok(mut supervised) => supervised
+            .get_mut(name)
+            .is_some_and(|entry| std::mem::take(&mut entry.restart_pending)),
+        Err(_) => false,
+    }
+}
+
+// A start by hand gets a fresh crash budget
+pub(crate) fn reset_crashes(name: &str) {
+    if let Ok(mut supervised) = SUPERVISED.lock() {
+        if let Some(entry) = supervised.get_mut(name) {
+            entry.crashes.clear();
+        }
+    }
+}
+
+fn exited(name: &'static str, pid: u32, exit: Option<Exit>, runtime: &Handle) {
+    {
+        let Ok(mut supervised) = SUPERVISED.lock() else { return };
+        let Some(entry) = supervised.get_mut(name) else { return };
+        if entry.pid != Some(pid) {
+            return;
+        }
+        entry.pid = None;
+        if entry.stopping {
+            return;
+        }
+    }
+
+    if tracked_pid(name).ok().flatten() == Some(pid) {
+        let _ = set_running(name, None);
+    }
+    let mut crash = ServiceCrash::new(name, Some(pid));
+    crash.exit_code = exit.and_then(|exit| exit.code);
+    crash.signal = exit.and_then(|exit| exit.signal);
+    report(name, crash, runtime);
+}
+
+fn auto_restart(name: &str) -> bool {
+    let Ok(base_path) = get_project_root() else { return false };
+    match (settings::load(&base_path), find_service(name)) {
+        (Ok(settings), Ok(service)) => service.service_settings(&settings).auto_restart,
+        _ => false,
+    }
+}
+
+pub(crate) fn backoff(crashes: usize) -> Duration {
+    let doublings = crashes.saturating_sub(1).min(16) as u32;
+    INITIAL_BACKOFF.saturating_mul(1 << doublings).min(MAX_BACKOFF)
+}
+
+// Count the crash, tell the UI, and schedule a restart when one is due
+fn report(name: &'static str, mut crash: ServiceCrash, runtime: &Handle) {
+    if let Ok(base_path) = get_project_root() {
+        crash.last_lines = logs::last_lines(&base_path, name, CRASH_LOG_LINES);
+    }
+    let auto_restart = auto_restart(name);
+
+    let delay = {
+        let Ok(mut supervised) = SUPERVISED.lock() else { return };
+        let entry = supervised.entry(name.to_string()).or_default();
+        let now = Instant::now();
+        entry.crashes.retain(|crashed| now.duration_since(*crashed) < CRASH_WINDOW);
+        entry.crashes.push(now);
+
+        crash.crashes = entry.crashes.len();
+        crash.gave_up = auto_restart && crash.crashes >= MAX_CRASHES;
+        entry.restart_pending = auto_restart && !crash.gave_up;
+        entry.restart_pending.then(|| backoff(crash.crashes))
+    };
+    crash.restart_in_secs = delay.map(|delay| delay.as_secs());
+    jobs::emit_event(CRASH_EVENT, crash);
+
+    if let Some(delay) = delay {
+        runtime.spawn(restart_after(name, delay));
+    }
+}
+
+async fn restart_after(name: &'static str, delay: Duration) {
+    tokio::time::sleep(delay).await;
+    // Stopped or started by hand in the meantime
+    if !cancel_restart(name) || tracked_pid(name).ok().flatten().is_some() {
+        return;
+    }
+    let Ok(service) = find_service(name) else { return };
+
+    let label = format!("Restarting {} after a crash", service.display_name());
+    if let Err(e) = jobs::run("service-start", label, |job| async move { start(service, &job).await }).await {
+        let mut crash = ServiceCrash::new(name, None);
+        crash.error = Some(e);
+        report(name, crash, &Handle::current());
+    }
+}
@@ -0,0 +1,504 @@
+// Persistent user settings, stored in config/devstackbox.toml. The file carries
+// a schema version; older files are migrated forward on load and written back.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::get_project_root;
+use crate::services::{self, ports};
+
+pub const SCHEMA_VERSION: u32 = 1;
+
+// Each entry migrates a settings table from version `index` to `index + 1`
+const MIGRATIONS: &[fn(&mut toml::Table)] = &[
+    // 0 -> 1: files written before schema versioning only lacked the version key
+    |_table| {},
+];
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub schema_version: u32,
+    pub paths: PathSettings,
+    pub php: PhpSettings,
+    pub mysql: MySqlSettings,
+    pub apache: ApacheSettings,
+    pub logs: LogSettings,
+    pub app: AppSettings,
+    // Named sets of services started together, see services::profiles
+    pub profiles: BTreeMap<String, ProfileSettings>,
+    // Per-project overrides, keyed by the project's directory under the document root
+    pub projects: BTreeMap<String, ProjectSettings>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PathSettings {
+    // Relative paths are resolved against the DevStackBox root
+    pub document_root: String,
+    pub mysql_data_dir: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PhpSettings {
+    pub active_version: String,
+    // Directory the release archives and sha256sum.txt are downloaded from
+    pub mirror: String,
+    // URL or local path (relative to the DevStackBox root) of a releases.json index
+    pub releases_index: String,
+    // Thread-safe builds are needed for Apache's mod_php, CGI/FastCGI use NTS
+    pub thread_safe: bool,
+    // FastCGI pools for projects pinned to their own version listen from here up
+    pub fastcgi_base_port: u16,
+}
+
+// Options shared by every managed service
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ServiceSettings {
+    pub port: u16,
+    pub startup_timeout_secs: u64,
+    // How long a graceful stop may take before the server is killed
+    #[serde(default = "default_stop_timeout")]
+    pub stop_timeout_secs: u64,
+    // Start the service again when it exits without being asked to
+    #[serde(default)]
+    pub auto_restart: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProjectSettings {
+    // Installed version ("8.1.29") or branch ("8.1", the newest installed patch)
+    pub php_version: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MySqlSettings {
+    #[serde(flatten)]
+    pub service: ServiceSettings,
+    pub bind_address: String,
+    pub max_connections: u32,
+    pub sql_mode: String,
+    pub character_set: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ApacheSettings {
+    #[serde(flatten)]
+    pub service: ServiceSettings,
+    pub server_name: String,
+}
+
+// Rotation of every log under management: the servers' own logs, their
+// captured console output and PHP's error_log
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogSettings {
+    // Rotate a log once it reaches this size, 0 to never rotate by size
+    pub max_size_mb: u64,
+    // Rotate a log this many days after the previous rotation, 0 to never rotate by age
+    pub max_age_days: u64,
+    // Rotated copies kept per log (error.log.1 ... error.log.<keep>)
+    pub keep: u32,
+    // gzip rotated copies (error.log.1.gz)
+    pub compress: bool,
+}
+
+// What closing the window does to services that are still running. Ask needs
+// a UI that answers "exit-requested", so it is not the default.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExitAction {
+    #[default]
+    Stop,
+    Keep,
+    Ask,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppSettings {
+    pub on_exit: ExitAction,
+    // Profile started when the app launches
+    pub auto_start_profile: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProfileSettings {
+    // Services in start order, as far as their dependencies allow
+    pub services: Vec<String>,
+    // Services that have to be ready before the keyed one starts
+    pub depends_on: BTreeMap<String, Vec<String>>,
+}
+
+fn default_stop_timeout() -> u64 {
+    30
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            schema_version: SCHEMA_VERSION,
+            paths: PathSettings::default(),
+            php: PhpSettings::default(),
+            mysql: MySqlSettings::default(),
+            apache: ApacheSettings::default(),
+            logs: LogSettings::default(),
+            app: AppSettings::default(),
+            profiles: BTreeMap::from([(
+                "default".to_string(),
+                ProfileSettings {
+                    services: vec!["mysql".to_string(), "apache".to_string()],
+                    depends_on: BTreeMap::from([("apache".to_string(), vec!["mysql".to_string()])]),
+                },
+            )]),
+            projects: BTreeMap::new(),
+        }
+    }
+}
+
+impl Default for PathSettings {
+    fn default() -> Self {
+        PathSettings {
+            document_root: "www".to_string(),
+            mysql_data_dir: "mysql/data".to_string(),
+        }
+    }
+}
+
+impl Default for PhpSettings {
+    fn default() -> Self {
+        PhpSettings {
+            active_version: "8.2".to_string(),
+            mirror: "https://windows.php.net/downloads/releases".to_string(),
+            releases_index: "https://windows.php.net/downloads/releases/releases.json".to_string(),
+            thread_safe: true,
+            fastcgi_base_port: 9100,
+        }
+    }
+}
+
+impl Default for MySqlSettings {
+    fn default() -> Self {
+        MySqlSettings {
+            // InnoDB crash recovery on a cold start can take a while, and so
+            // can flushing the buffer pool on shutdown
+            service: ServiceSettings { port: 3306, startup_timeout_secs: 60, stop_timeout_secs: 60, auto_restart: false },
+            bind_address: "127.0.0.1".to_string(),
+            max_connections: 100,
+            sql_mode: "STRICT_TRANS_TABLES,NO_ZERO_DATE,NO_ZERO_IN_DATE,ERROR_FOR_DIVISION_BY_ZERO".to_string(),
+            character_set: "utf8mb4".to_string(),
+        }
+    }
+}
+
+impl Default for ApacheSettings {
+    fn default() -> Self {
+        ApacheSettings {
+            service: ServiceSettings { port: 80, startup_timeout_secs: 30, stop_timeout_secs: 30, auto_restart: false },
+            server_name: "localhost".to_string(),
+        }
+    }
+}
+
+impl Default for LogSettings {
+    fn default() -> Self {
+        LogSettings {
+            max_size_mb: 10,
+            max_age_days: 7,
+            keep: 5,
+            compress: false,
+        }
+    }
+}
+
+impl Settings {
+    pub fn document_root(&self, base_path: &Path) -> PathBuf {
+        base_path.join(&self.paths.document_root)
+    }
+
+    pub fn mysql_data_dir(&self, base_path: &Path) -> PathBuf {
+        base_path.join(&self.paths.mysql_data_dir)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let mut errors = Vec::new();
+
+        for (name, service) in [("MySQL", &self.mysql.service), ("Apache", &self.apache.service)] {
+            if service.port == 0 {
+                errors.push(format!("{} port must be between 1 and 65535", name));
+            }
+            if !(1..=600).contains(&service.startup_timeout_secs) {
+                errors.push(format!("{} startup timeout must be between 1 and 600 seconds", name));
+            }
+            if !(1..=600).contains(&service.stop_timeout_secs) {
+                errors.push(format!("{} stop timeout must be between 1 and 600 seconds", name));
+            }
+        }
+        if self.mysql.service.port == self.apache.service.port {
+            errors.push(format!("MySQL and Apache cannot share port {}", self.mysql.service.port));
+        }
+
+        if self.paths.document_root.trim().is_empty() {
+            errors.push("Document root cannot be empty".to_string());
+        }
+        if self.paths.mysql_data_dir.trim().is_empty() {
+            errors.push("MySQL data directory cannot be empty".to_string());
+        }
+
+        if !is_php_version(&self.php.active_version) {
+            errors.push(format!("'{}' is not a PHP version like 8.2", self.php.active_version));
+        }
+        if self.php.fastcgi_base_port < 1024 {
+            errors.push("PHP FastCGI base port must be between 1024 and 65535".to_string());
+        }
+        for (project, overrides) in &self.projects {
+            if !is_project_name(project) {
+                errors.push(format!("'{}' is not a project directory name", project));
+            }
+            if !is_php_version(&overrides.php_version) {
+                errors.push(format!("'{}' is not a PHP version like 8.2 for project {}", overrides.php_version, project));
+            }
+        }
+
+        if !(self.php.mirror.starts_with("https://") || self.php.mirror.starts_with("http://")) {
+            errors.push(format!("'{}' is not an http(s) URL", self.php.mirror));
+        }
+        if self.php.releases_index.trim().is_empty() {
+            errors.push("PHP releases index cannot be empty".to_string());
+        }
+
+        if self.mysql.bind_address.parse::<std::net::IpAddr>().is_err() {
+            errors.push(format!("'{}' is not a valid bind address", self.mysql.bind_address));
+        }
+        if !(1..=100_000).contains(&self.mysql.max_connections) {
+            errors.push("MySQL max_connections must be between 1 and 100000".to_string());
+        }
+        if self.mysql.character_set.is_empty() || !self.mysql.character_set.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            errors.push(format!("'{}' is not a valid character set name", self.mysql.character_set));
+        }
+        if self.mysql.sql_mode.contains(['"', '\n']) {
+            errors.push("MySQL sql_mode cannot contain quotes or line breaks".to_string());
+        }
+
+        if self.apache.server_name.is_empty() || self.apache.server_name.contains(char::is_whitespace) {
+            errors.push(format!("'{}' is not a valid server name", self.apache.server_name));
+        }
+
+        if self.logs.max_size_mb > 100_000 {
+            errors.push("Log rotation size must be at most 100000 MB".to_string());
+        }
+        if self.logs.max_age_days > 3650 {
+            errors.push("Log rotation age must be at most 3650 days".to_string());
+        }
+        if !(1..=100).contains(&self.logs.keep) {
+            errors.push("Rotated logs kept must be between 1 and 100".to_string());
+        }
+
+        for (name, profile) in &self.profiles {
+            if name.trim().is_empty() {
+                errors.push("Profile names cannot be empty".to_string());
+            }
+            if let Err(e) = services::profiles::start_order(name, profile) {
+                errors.push(e);
+            }
+        }
+        if let Some(name) = &self.app.auto_start_profile {
+            if !self.profiles.contains_key(name) {
+                errors.push(format!("Auto-start profile '{}' does not exist", name));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join("; "))
+        }
+    }
+}
+
+fn is_php_version(version: &str) -> bool {
+    version.split('.').count() >= 2 && version.split('.').all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()))
+}
+
+// A single directory directly under the document root
+pub fn is_project_name(name: &str) -> bool {
+    !name.is_empty() && name != "." && name != ".." && !name.contains(['/', '\\', '"'])
+}
+
+pub fn settings_path(base_path: &Path) -> PathBuf {
+    base_path.join("config").join("devstackbox.toml")
+}
+
+// Bring a parsed settings table up to SCHEMA_VERSION
+fn migrate(table: &mut toml::Table) -> Result<bool, String> {
+    let version = match table.get("schema_version") {
+        Some(value) => value
+            .as_integer()
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or("schema_version must be a non-negative integer")?,
+        None => 0,
+    };
+
+    if version > SCHEMA_VERSION {
+        return Err(format!(
+            "Settings file uses schema version {} but this DevStackBox only understands up to {}",
+            version, SCHEMA_VERSION
+        ));
+    }
+
+    for migration in &MIGRATIONS[version as usize..] {
+        migration(table);
+    }
+    table.insert("schema_version".to_string(), toml::Value::Integer(SCHEMA_VERSION.into()));
+    Ok(version < SCHEMA_VERSION)
+}
+
+// Maps keyed by names the user chose. They belong to the user as a whole: the
+// defaults only seed them when the key is absent, so a removed default profile
+// or dependency stays removed.
+const USER_KEYED: &[&str] = &["profiles", "projects"];
+
+// Fill keys missing from a hand-edited or older file with their defaults.
+// Nested tables are merged rather than replaced, so `[mysql]` with only a port
+// still gets the default timeout (serde's `default` does not reach flattened fields)
+fn fill_defaults(table: &mut toml::Table, defaults: toml::Table) {
+    for (key, default) in defaults {
+        if USER_KEYED.contains(&key.as_str()) && table.contains_key(&key) {
+            continue;
+        }
+        match (table.get_mut(&key), default) {
+            (Some(toml::Value::Table(existing)), toml::Value::Table(default)) => fill_defaults(existing, default),
+            (Some(_), _) => {}
+            (None, default) => {
+                table.insert(key, default);
+            }
+        }
+    }
+}
+
+// Settings for a fresh install: defaults, plus whatever ports the existing
+// config files already use so earlier choices are not lost
+fn initial_settings(base_path: &Path) -> Settings {
+    let mut settings = Settings::default();
+    for service in services::registry() {
+        let configured = std::fs::read_to_string(service.config_path(base_path))
+            .ok()
+            .and_then(|config| service.parse_port(&config));
+        if let Some(port) = configured {
+            service.service_settings_mut(&mut settings).port = port;
+        }
+    }
+    settings
+}
+
+pub fn load(base_path: &Path) -> Result<Settings, String> {
+    let path = settings_path(base_path);
+    if !path.exists() {
+        let settings = initial_settings(base_path);
+        save(base_path, &settings)?;
+        return Ok(settings);
+    }
+
+    let content = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let mut table: toml::Table = content
+        .parse()
+        .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+    let migrated = migrate(&mut table)?;
+    let defaults = toml::Table::try_from(Settings::default()).map_err(|e| e.to_string())?;
+    fill_defaults(&mut table, defaults);
+
+    let settings: Settings = toml::Value::Table(table)
+        .try_into()
+        .map_err(|e| format!("Invalid settings in {}: {}", path.display(), e))?;
+    if migrated {
+        save(base_path, &settings)?;
+    }
+    Ok(settings)
+}
+
+pub fn save(base_path: &Path, settings: &Settings) -> Result<(), String> {
+    let path = settings_path(base_path);
+    let content = toml::to_string_pretty(settings).map_err(|e| e.to_string())?;
+    std::fs::create_dir_all(base_path.join("config")).map_err(|e| e.to_string())?;
+
+    // Write to a temporary file first so a crash never leaves half a settings file
+    let temp_path = path.with_extension("toml.tmp");
+    std::fs::write(&temp_path, content).map_err(|e| e.to_string())?;
+    std::fs::rename(&temp_path, &path).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_settings() -> Result<Settings, String> {
+    load(&get_project_root()?)
+}
+
+#[tauri::command]
+pub async fn update_settings(settings: Settings) -> Result<Settings, String> {
+    let base_path = get_project_root()?;
+    let mut settings = settings;
+    settings.schema_version = SCHEMA_VERSION;
+    settings.validate()?;
+
+    let previous = load(&base_path)?;
+    for service in services::registry() {
+        let changed = service.service_settings(&previous).port != service.service_settings(&settings).port;
+        if changed && services::tracked_pid(service.name())?.is_some() {
+            return Err(format!("Stop {} before changing its port", service.display_name()));
+        }
+    }
+
+    save(&base_path, &settings)?;
+
+    // Carry the changes into the existing config files without regenerating them
+    for service in services::registry() {
+        let port = service.service_settings(&settings).port;
+        if service.service_settings(&previous).port != port {
+            ports::apply_port(service.as_ref(), &base_path, port)?;
+        }
+        service.apply_settings(&base_path, &previous, &settings)?;
+    }
+
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_base(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("devstackbox-settings-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("config")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn user_keyed_maps_are_not_merged_with_defaults() {
+        let base_path = temp_base("user-keyed");
+        let content = "schema_version = 1\n\n[mysql]\nport = 3307\n\n[profiles.web]\nservices = [\"apache\"]\n";
+        std::fs::write(settings_path(&base_path), content).unwrap();
+
+        let settings = load(&base_path).unwrap();
+        assert_eq!(settings.profiles.keys().collect::<Vec<_>>(), ["web"]);
+        assert!(settings.profiles["web"].depends_on.is_empty());
+        // Ordinary sections still get their missing keys
+        assert_eq!(settings.mysql.service.port, 3307);
+        assert_eq!(settings.mysql.service.startup_timeout_secs, Settings::default().mysql.service.startup_timeout_secs);
+        let _ = std::fs::remove_dir_all(&base_path);
+    }
+
+    #[test]
+    fn absent_user_keyed_maps_get_the_defaults() {
+        let base_path = temp_base("absent");
+        std::fs::write(settings_path(&base_path), "schema_version = 1\n").unwrap();
+
+        let settings = load(&base_path).unwrap();
+        assert_eq!(settings.profiles, Settings::default().profiles);
+        let _ = std::fs::remove_dir_all(&base_path);
+    }
+}
@@ -0,0 +1,78 @@
+// What closing the window does to services that are still running, following
+// the app.on_exit setting: stop them, leave them running (the next launch
+// adopts them), or ask. Asking emits "exit-requested" with the running
+// services; the UI answers through quit_app. Closing the window a second time
+// while the question is open leaves the services running.
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, CloseRequestApi, Manager, Window};
+
+use crate::settings::{self, ExitAction};
+use crate::{get_project_root, jobs, services};
+
+pub const EXIT_EVENT: &str = "exit-requested";
+
+// The app is on its way out, closing must not be held up again
+static EXITING: AtomicBool = AtomicBool::new(false);
+
+// The UI has been asked and not answered yet
+static ASKED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Clone, Serialize)]
+pub struct ExitRequest {
+    pub running: Vec<String>,
+}
+
+fn exit_action() -> ExitAction {
+    get_project_root()
+        .and_then(|base_path| settings::load(&base_path))
+        .map(|settings| settings.app.on_exit)
+        .unwrap_or_default()
+}
+
+// Called for the window's CloseRequested event
+pub fn on_close_requested(window: &Window, api: &CloseRequestApi) {
+    if EXITING.load(Ordering::SeqCst) {
+        return;
+    }
+    let running = services::running_services();
+    if running.is_empty() {
+        return;
+    }
+
+    match exit_action() {
+        ExitAction::Keep => {}
+        ExitAction::Stop => {
+            api.prevent_close();
+            tauri::async_runtime::spawn(quit(window.app_handle().clone(), true));
+        }
+        ExitAction::Ask if ASKED.swap(true, Ordering::SeqCst) => {}
+        ExitAction::Ask => {
+            api.prevent_close();
+            jobs::emit_event(EXIT_EVENT, ExitRequest { running });
+        }
+    }
+}
+
+async fn quit(app: AppHandle, stop_services: bool) {
+    EXITING.store(true, Ordering::SeqCst);
+    if stop_services {
+        services::stop_all().await;
+    }
+    app.exit(0);
+}
+
+// The UI's answer to "exit-requested"
+#[tauri::command]
+pub async fn quit_app(app: AppHandle, stop_services: bool) -> Result<(), String> {
+    quit(app, stop_services).await;
+    Ok(())
+}
+
+// The UI decided not to quit after all
+#[tauri::command]
+pub async fn cancel_quit() -> Result<(), String> {
+    ASKED.store(false, Ordering::SeqCst);
+    Ok(())
+}
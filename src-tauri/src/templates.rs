@@ -0,0 +1,91 @@
+// Minimal config template engine. Templates use `{{name}}` placeholders, and
+// `# BEGIN USER <block>` / `# END USER <block>` markers delimit hand-written
+// sections that are carried over from the existing file on regeneration.
+
+use std::collections::HashMap;
+
+const BLOCK_BEGIN: &str = "# BEGIN USER ";
+const BLOCK_END: &str = "# END USER ";
+
+pub type Vars = HashMap<&'static str, String>;
+
+// Substitute every `{{name}}`; unknown names are an error so a typo in a user
+// template is reported instead of silently rendering an empty value
+pub fn render(template: &str, vars: &Vars) -> Result<String, String> {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        output.push_str(&rest[..start]);
+        let line = template[..template.len() - rest.len() + start].matches('\n').count() + 1;
+        let end = rest[start..]
+            .find("}}")
+            .ok_or_else(|| format!("Unclosed '{{{{' on line {}", line))?;
+
+        let name = rest[start + 2..start + end].trim();
+        let value = vars
+            .get(name)
+            .ok_or_else(|| format!("Unknown template variable '{}' on line {}", name, line))?;
+        output.push_str(value);
+        rest = &rest[start + end + 2..];
+    }
+
+    output.push_str(rest);
+    Ok(output)
+}
+
+// Named USER blocks and their bodies, in file order
+fn user_blocks(content: &str) -> Vec<(String, Vec<&str>)> {
+    let mut blocks = Vec::new();
+    let mut current: Option<(String, Vec<&str>)> = None;
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if let Some(name) = trimmed.strip_prefix(BLOCK_BEGIN) {
+            current = Some((name.trim().to_string(), Vec::new()));
+        } else if let Some(name) = trimmed.strip_prefix(BLOCK_END) {
+            if let Some(block) = current.take().filter(|(open, _)| open == name.trim()) {
+                blocks.push(block);
+            }
+        } else if let Some((_, body)) = current.as_mut() {
+            body.push(line);
+        }
+    }
+    blocks
+}
+
+// Fill the USER blocks of a freshly rendered config with the bodies found in
+// the existing file. Blocks the template no longer has are kept at the end.
+pub fn merge_user_blocks(rendered: &str, existing: &str) -> String {
+    let mut preserved = user_blocks(existing);
+    let mut output = Vec::new();
+    let mut skipping = false;
+
+    for line in rendered.lines() {
+        let trimmed = line.trim();
+        if let Some(name) = trimmed.strip_prefix(BLOCK_BEGIN) {
+            output.push(line.to_string());
+            if let Some(index) = preserved.iter().position(|(open, _)| open == name.trim()) {
+                let (_, body) = preserved.remove(index);
+                output.extend(body.into_iter().map(str::to_string));
+                skipping = true;
+            }
+        } else if trimmed.starts_with(BLOCK_END) {
+            output.push(line.to_string());
+            skipping = false;
+        } else if !skipping {
+            output.push(line.to_string());
+        }
+    }
+
+    for (name, body) in preserved.into_iter().filter(|(_, body)| !body.is_empty()) {
+        output.push(String::new());
+        output.push(format!("{}{}", BLOCK_BEGIN, name));
+        output.extend(body.into_iter().map(str::to_string));
+        output.push(format!("{}{}", BLOCK_END, name));
+    }
+
+    let mut merged = output.join("\n");
+    merged.push('\n');
+    merged
+}
@@ -31,8 +31,8 @@ export function ServiceManager({
   const checkServiceStatus = async () => {
     try {
       const [apache, mysql, php] = await Promise.all([
-        invoke<ServiceStatus>("get_apache_status"),
-        invoke<ServiceStatus>("get_mysql_status"),
+        invoke<ServiceStatus>("service_status", { name: "apache" }),
+        invoke<ServiceStatus>("service_status", { name: "mysql" }),
         invoke<ServiceStatus>("get_php_status"),
       ]);
       
@@ -47,12 +47,13 @@ export function ServiceManager({
     setLoading(service);
     try {
       let result: boolean;
-      if (service === "apache") {
-        result = await invoke<boolean>("toggle_apache");
-      } else if (service === "mysql") {
-        result = await invoke<boolean>("toggle_mysql");
-      } else {
+      if (service === "php") {
         result = await invoke<boolean>("toggle_php");
+      } else if ((await invoke<ServiceStatus>("service_status", { name: service })).running) {
+        await invoke<boolean>("stop_service", { name: service });
+        result = false;
+      } else {
+        result = await invoke<boolean>("start_service", { name: service });
       }
       
       await checkServiceStatus();